@@ -0,0 +1,37 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Records the git commit the benchmarker is built from, and whether the checkout had
+/// uncommitted changes, for the run manifest. Both are empty outside a git checkout.
+fn main() {
+    let commit = git(&["rev-parse", "HEAD"]).unwrap_or_default();
+    let dirty = git(&["status", "--porcelain"])
+        .map(|status| (!status.is_empty()).to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=GIT_DIRTY={}", dirty);
+
+    // Edits to the sources change the dirty flag; commits and checkouts change HEAD and the index.
+    for path in [
+        "src",
+        "plans",
+        "Cargo.toml",
+        "build.rs",
+        ".git/HEAD",
+        ".git/index",
+        ".git/refs",
+    ] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
+
+/// Trimmed stdout of a git command run in the crate's checkout, or `None` if it failed.
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,69 @@
+use crate::benchmark::output::INDEX_FILE_NAME;
+use crate::metrics::pcm_csv::{parse_pcm_file, summarize, PcmSummary};
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Summary of one PCM measurement file, or why it could not be read.
+pub struct FileAnalysis {
+    pub path: PathBuf,
+    pub summary: Result<PcmSummary, String>,
+}
+
+/// Summarizes every PCM CSV in `paths`. A run directory contributes the files its index lists;
+/// other directories are scanned (non-recursively) for `.csv` files.
+pub fn analyze_paths(paths: &[PathBuf]) -> Result<Vec<FileAnalysis>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.join(INDEX_FILE_NAME).is_file() {
+            files.extend(indexed_files(path)?);
+        } else if path.is_dir() {
+            files.extend(csv_files(path)?);
+        } else if path.exists() {
+            files.push(path.clone());
+        } else {
+            return Err(format!("{} does not exist", path.display()).into());
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            let summary = parse_pcm_file(&path)
+                .map(|samples| summarize(&samples))
+                .map_err(|e| e.to_string());
+            FileAnalysis { path, summary }
+        })
+        .collect())
+}
+
+/// The `file` column of a run's index; measurements without a monitor file are left out.
+#[derive(Deserialize)]
+struct IndexedFile {
+    file: Option<String>,
+}
+
+fn indexed_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let index = dir.join(INDEX_FILE_NAME);
+    let mut reader = csv::Reader::from_path(&index)
+        .map_err(|e| format!("Failed to read {}: {}", index.display(), e))?;
+    let mut files = Vec::new();
+    for row in reader.deserialize() {
+        let row: IndexedFile = row?;
+        if let Some(file) = row.file {
+            files.push(dir.join(file));
+        }
+    }
+    Ok(files)
+}
+
+fn csv_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "csv"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,318 @@
+use crate::benchmark::plan::BenchmarkPlan;
+use crate::benchmark::results::Results;
+use crate::metrics::Measurement;
+use fs_extra::dir::create_all;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File the runner records its progress in, inside the results directory.
+pub const STATE_FILE_NAME: &str = "run_state.json";
+
+/// Everything a suite has measured so far, saved after every window so an interrupted
+/// run can pick up where it stopped. Measurements are stored without their energy samples,
+/// which the results don't use and which would make every save rewrite all of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RunState {
+    /// The plan as run, after filters and overrides; a resumed run must match it.
+    plan: Value,
+    order_seed: Option<u64>,
+    idle: Vec<IdleRecord>,
+    cells: Vec<CellRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IdleRecord {
+    epoch: u8,
+    measurement: Measurement,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CellRecord {
+    epoch: u8,
+    database: String,
+    depth: u8,
+    test_id: String,
+    measurement: Measurement,
+}
+
+/// A run state file and its contents.
+pub struct Checkpoint {
+    path: PathBuf,
+    state: RunState,
+}
+
+impl Checkpoint {
+    /// Starts a new suite, replacing any state file left at `path`.
+    pub fn create(
+        path: &Path,
+        plan: &BenchmarkPlan,
+        order_seed: Option<u64>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let checkpoint = Checkpoint {
+            path: path.to_path_buf(),
+            state: RunState {
+                plan: serde_json::to_value(plan)?,
+                order_seed,
+                idle: Vec::new(),
+                cells: Vec::new(),
+            },
+        };
+        checkpoint.save()?;
+        Ok(checkpoint)
+    }
+
+    /// Loads the state of an interrupted suite, which must have been started with the same
+    /// plan, filters and monitor.
+    pub fn resume(path: &Path, plan: &BenchmarkPlan) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read run state {}: {}", path.display(), e))?;
+        let state: RunState = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid run state {}: {}", path.display(), e))?;
+        if state.plan != serde_json::to_value(plan)? {
+            return Err(format!(
+                "Run state {} was written for a different plan, filter or monitor",
+                path.display()
+            )
+            .into());
+        }
+        Ok(Checkpoint {
+            path: path.to_path_buf(),
+            state,
+        })
+    }
+
+    pub fn order_seed(&self) -> Option<u64> {
+        self.state.order_seed
+    }
+
+    pub fn completed_cells(&self) -> usize {
+        self.state.cells.len()
+    }
+
+    pub fn has_idle(&self, epoch: u8) -> bool {
+        self.state.idle.iter().any(|idle| idle.epoch == epoch)
+    }
+
+    pub fn is_done(&self, epoch: u8, database: &str, depth: u8, test_id: &str) -> bool {
+        self.state.cells.iter().any(|cell| {
+            cell.epoch == epoch
+                && cell.database == database
+                && cell.depth == depth
+                && cell.test_id == test_id
+        })
+    }
+
+    pub fn record_idle(
+        &mut self,
+        epoch: u8,
+        measurement: &Measurement,
+    ) -> Result<(), Box<dyn Error>> {
+        self.state.idle.push(IdleRecord {
+            epoch,
+            measurement: without_samples(measurement),
+        });
+        self.save()
+    }
+
+    pub fn record_cell(
+        &mut self,
+        epoch: u8,
+        database: &str,
+        depth: u8,
+        test_id: &str,
+        measurement: &Measurement,
+    ) -> Result<(), Box<dyn Error>> {
+        self.state.cells.push(CellRecord {
+            epoch,
+            database: database.to_string(),
+            depth,
+            test_id: test_id.to_string(),
+            measurement: without_samples(measurement),
+        });
+        self.save()
+    }
+
+    /// Replays every recorded measurement into `results`, in the order it was taken.
+    pub fn restore(&self, plan: &BenchmarkPlan, results: &mut Results) {
+        for idle in &self.state.idle {
+            results.record_idle(idle.epoch, idle.measurement.clone());
+        }
+        for cell in &self.state.cells {
+            // The plan matched when the state was loaded, so every test id is in it.
+            if let Some(test) = plan.test_cases.iter().find(|test| test.id == cell.test_id) {
+                results.record_run(
+                    test,
+                    &cell.database,
+                    cell.depth,
+                    cell.epoch,
+                    cell.measurement.clone(),
+                );
+            }
+        }
+    }
+
+    /// Writes to a temporary file first so an interrupted write never loses the previous state.
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent() {
+            create_all(dir, false)?;
+        }
+        let temporary = self.path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_string(&self.state)?)?;
+        fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+fn without_samples(measurement: &Measurement) -> Measurement {
+    Measurement {
+        samples: Vec::new(),
+        ..measurement.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::EnergySample;
+    use serde_json::json;
+    use std::time::{Duration, SystemTime};
+
+    fn plan(seed: Option<u64>) -> BenchmarkPlan {
+        serde_json::from_value(json!({
+            "monitor": "simulated-fixed",
+            "epochs": [1, 2],
+            "idle_duration_secs": 1,
+            "num_docs": 10,
+            "order": "shuffled",
+            "seed": seed,
+            "databases": [{ "name": "MongoDB" }, { "name": "PostgreSQL" }],
+            "datasets": [{ "depth": 3 }],
+            "test_cases": [
+                { "id": "1A", "name": "read", "read_ratio": 1, "write_ratio": 0, "operations": 10 },
+                { "id": "1B", "name": "write", "read_ratio": 0, "write_ratio": 1, "operations": 10 }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn measurement(name: &str, joules: f64) -> Measurement {
+        let mut measurement = Measurement::new(
+            name.to_string(),
+            "simulated",
+            SystemTime::now(),
+            Duration::from_secs(2),
+        );
+        measurement.package_joules = Some(joules);
+        measurement.samples = vec![
+            EnergySample {
+                elapsed: Duration::from_secs(1),
+                package_joules: joules / 2.0,
+                core_joules: None,
+                dram_joules: None,
+            };
+            2
+        ];
+        measurement
+    }
+
+    fn state_file(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("checkpoint_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(STATE_FILE_NAME)
+    }
+
+    #[test]
+    fn resume_picks_up_the_recorded_windows_and_seed() {
+        let path = state_file("resume");
+        let plan = plan(Some(42));
+        let mut checkpoint = Checkpoint::create(&path, &plan, Some(42)).unwrap();
+        checkpoint
+            .record_idle(1, &measurement("C1_idle_epoch_1", 20.0))
+            .unwrap();
+        checkpoint
+            .record_cell(1, "MongoDB", 3, "1A", &measurement("1A", 50.0))
+            .unwrap();
+
+        let resumed = Checkpoint::resume(&path, &plan).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(resumed.order_seed(), Some(42));
+        assert_eq!(resumed.completed_cells(), 1);
+        assert!(resumed.has_idle(1));
+        assert!(!resumed.has_idle(2));
+        assert!(resumed.is_done(1, "MongoDB", 3, "1A"));
+        assert!(!resumed.is_done(2, "MongoDB", 3, "1A"));
+        assert!(!resumed.is_done(1, "PostgreSQL", 3, "1A"));
+        assert!(!resumed.is_done(1, "MongoDB", 3, "1B"));
+    }
+
+    #[test]
+    fn restore_replays_idle_and_cell_measurements() {
+        let path = state_file("restore");
+        let plan = plan(Some(42));
+        let mut checkpoint = Checkpoint::create(&path, &plan, Some(42)).unwrap();
+        checkpoint
+            .record_idle(1, &measurement("C1_idle_epoch_1", 20.0))
+            .unwrap();
+        checkpoint
+            .record_cell(1, "PostgreSQL", 3, "1B", &measurement("1B", 50.0))
+            .unwrap();
+
+        let resumed = Checkpoint::resume(&path, &plan).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        let mut results = Results::new();
+        resumed.restore(&plan, &mut results);
+
+        assert_eq!(results.idle_watts(1), Some(10.0));
+        let runs = results.runs();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].test_id, "1B");
+        assert_eq!(runs[0].database, "PostgreSQL");
+        assert_eq!(runs[0].measurement.package_joules, Some(50.0));
+    }
+
+    #[test]
+    fn resume_rejects_a_different_plan() {
+        let path = state_file("mismatch");
+        Checkpoint::create(&path, &plan(Some(42)), Some(42)).unwrap();
+
+        let result = Checkpoint::resume(&path, &plan(Some(7)));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn a_drawn_seed_is_kept_for_the_resume() {
+        let path = state_file("seed");
+        let plan = plan(None);
+        Checkpoint::create(&path, &plan, Some(1234)).unwrap();
+
+        let resumed = Checkpoint::resume(&path, &plan).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(resumed.order_seed(), Some(1234));
+    }
+
+    #[test]
+    fn the_state_file_leaves_out_energy_samples() {
+        let path = state_file("samples");
+        let plan = plan(Some(42));
+        let mut checkpoint = Checkpoint::create(&path, &plan, Some(42)).unwrap();
+        checkpoint
+            .record_cell(1, "MongoDB", 3, "1A", &measurement("1A", 50.0))
+            .unwrap();
+
+        let state: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(state["cells"][0]["measurement"]["samples"], json!([]));
+        assert_eq!(
+            state["cells"][0]["measurement"]["package_joules"],
+            json!(50.0)
+        );
+    }
+}
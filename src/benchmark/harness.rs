@@ -1,83 +1,754 @@
+use crate::benchmark::output::measurement_file_name;
+use crate::benchmark::plan::{Cooldown, Dataset, TestCase};
+use crate::benchmark::workload::{Bound, OpMix, OpenLoop, Operation};
 use crate::databases::database::Database;
-use crate::metrics::{start_power_monitoring, stop_power_monitoring};
+use crate::metrics::attribution::CpuShares;
+use crate::metrics::{
+    CgroupCollector, CgroupUsage, EnergyAttribution, Measurement, OperationLatencies, PowerMonitor,
+    ProcessSampler, ThermalSampler, ThermalTelemetry,
+};
 use serde_json::Value;
 use std::error::Error;
 use std::fs;
-use std::time::Duration;
-use tokio::time::{sleep, Instant};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::signal::ctrl_c;
+use tokio::sync::{watch, Notify};
+use tokio::time::{sleep, sleep_until};
 
-pub async fn run_read_heavy(
-    db: &dyn Database,
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(200);
+/// How late an open-loop operation may start before it counts as a missed deadline.
+const DEADLINE_TOLERANCE: Duration = Duration::from_millis(1);
+/// How often a time- or energy-bounded workload checks whether it should stop.
+const BOUND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Span over which package power is averaged while waiting for it to stabilize.
+const STABILITY_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Set once the user presses Ctrl-C. Open measurement windows are closed early and marked
+/// aborted, and the runner stops before starting anything new.
+#[derive(Clone)]
+pub struct Interrupt {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Interrupt {
+    /// Starts listening for Ctrl-C. A second Ctrl-C exits without cleaning up.
+    pub fn install() -> Self {
+        let (sender, receiver) = watch::channel(false);
+        tokio::spawn(async move {
+            if ctrl_c().await.is_err() {
+                // No signal handler, so never interrupted; keep the sender alive.
+                std::future::pending::<()>().await;
+            }
+            eprintln!("Interrupted, cleaning up (press Ctrl-C again to exit immediately)");
+            let _ = sender.send(true);
+            if ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+            std::future::pending::<()>().await;
+        });
+        Interrupt { receiver }
+    }
+
+    pub fn is_set(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Completes once the interrupt is set.
+    pub async fn wait(&self) {
+        let mut receiver = self.receiver.clone();
+        if receiver.wait_for(|&set| set).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+pub async fn run_test_case(
+    db: &mut Arc<dyn Database>,
+    monitor: &mut dyn PowerMonitor,
+    test: &TestCase,
     depth: u8,
-    use_index: bool,
     num_docs: usize,
     epoch: u8,
-) -> Result<u128, Box<dyn Error>> {
-    let measurement_name = format!(
-        "{}_read_heavy_depth_{}_epoch_{}{}.csv",
-        db.name(),
-        depth,
-        epoch,
-        if use_index { "_with_index" } else { "" }
-    );
+    interrupt: &Interrupt,
+) -> Result<Measurement, Box<dyn Error>> {
+    let measurement_name =
+        measurement_file_name(&test.id, &format!("{}_depth_{}", db.name(), depth), epoch);
 
-    let read_ops =  1000;
-    let write_ops = 200;
+    run_measured(
+        db,
+        monitor,
+        test,
+        depth,
+        num_docs,
+        &measurement_name,
+        interrupt,
+    )
+    .await
+}
 
-    let start_time = Instant::now();
-    db.run_queries(depth, read_ops, write_ops, use_index, num_docs, &measurement_name)
+async fn run_measured(
+    db: &mut Arc<dyn Database>,
+    monitor: &mut dyn PowerMonitor,
+    test: &TestCase,
+    depth: u8,
+    num_docs: usize,
+    measurement_name: &str,
+    interrupt: &Interrupt,
+) -> Result<Measurement, Box<dyn Error>> {
+    if test.use_index {
+        db.prepare_indexes(depth).await?;
+    }
+    exclusive(db)?
+        .prepare_workload(depth, num_docs, test.clients)
         .await?;
 
-    let duration = start_time.elapsed().as_millis();
-    Ok(duration)
+    let window = open_window(monitor, measurement_name, Some(&**db)).await?;
+    let result = run_workload(Arc::clone(db), &mut *monitor, test, interrupt).await;
+    // Always close the window so a failed workload doesn't leave the monitor running.
+    let mut measurement = close_window(monitor, window).await?;
+    let outcome = result?;
+    measurement.latency = Some(outcome.latency);
+    measurement.aborted = outcome.interrupted;
+    if test.open_loop().is_some() {
+        measurement.missed_deadlines = Some(outcome.missed_deadlines);
+    }
+
+    Ok(measurement)
 }
 
-pub async fn run_write_heavy(
-    db: &dyn Database,
-    depth: u8,
-    use_index: bool,
-    num_docs: usize,
+#[derive(Default)]
+struct WorkloadOutcome {
+    latency: OperationLatencies,
+    missed_deadlines: u64,
+    interrupted: bool,
+}
+
+/// Everything the clients of one workload share.
+struct Workload {
+    db: Arc<dyn Database>,
+    mix: OpMix,
+    clients: usize,
+    pacing: Option<OpenLoop>,
+    /// Repeat the schedule until `stop` is set instead of running it once.
+    repeat: bool,
+    start: Instant,
+    stop: AtomicBool,
+    /// Signalled once every client has finished.
+    finished: Notify,
+}
+
+/// Runs the test's workload on `test.clients` connections, each driven by its own task, until
+/// its bound is reached. The shared schedule is dealt out round-robin, so every backend sees
+/// the same workload whatever the concurrency.
+async fn run_workload(
+    db: Arc<dyn Database>,
+    monitor: &mut dyn PowerMonitor,
+    test: &TestCase,
+    interrupt: &Interrupt,
+) -> Result<WorkloadOutcome, Box<dyn Error>> {
+    let bound = test.bound();
+    let workload = Arc::new(Workload {
+        db,
+        mix: test.op_mix(),
+        clients: test.clients,
+        pacing: test.open_loop(),
+        // An open-loop schedule already holds every operation due within the duration.
+        repeat: bound != Bound::Operations && test.open_loop().is_none(),
+        start: Instant::now(),
+        stop: AtomicBool::new(false),
+        finished: Notify::new(),
+    });
+
+    let workers = async {
+        // Separate tasks let client-side work such as decoding results use every worker thread.
+        let tasks: Vec<_> = (0..workload.clients)
+            .map(|client| {
+                let workload = Arc::clone(&workload);
+                tokio::spawn(async move {
+                    let result = run_client(&workload, client)
+                        .await
+                        .map_err(|e| e.to_string());
+                    if result.is_err() {
+                        workload.stop.store(true, Ordering::Relaxed);
+                    }
+                    result
+                })
+            })
+            .collect();
+        let mut outcomes = Vec::with_capacity(tasks.len());
+        let mut error = None;
+        // Wait for every task, so none still holds the database when this returns.
+        for task in tasks {
+            match task.await {
+                Ok(Ok(outcome)) => outcomes.push(outcome),
+                Ok(Err(e)) => {
+                    error.get_or_insert(e);
+                }
+                Err(e) => {
+                    workload.stop.store(true, Ordering::Relaxed);
+                    error.get_or_insert(format!("Workload client failed: {}", e));
+                }
+            }
+        }
+        workload.stop.store(true, Ordering::Relaxed);
+        workload.finished.notify_one();
+        match error {
+            Some(e) => Err(e),
+            None => Ok(outcomes),
+        }
+    };
+    let watcher = async {
+        tokio::select! {
+            result = watch_bound(&workload, monitor, bound) => result.map(|()| false),
+            _ = interrupt.wait() => {
+                workload.stop.store(true, Ordering::Relaxed);
+                Ok(true)
+            }
+        }
+    };
+    let (workers, interrupted) = tokio::join!(workers, watcher);
+    let workers = workers?;
+
+    let mut outcome = WorkloadOutcome {
+        interrupted: interrupted?,
+        ..WorkloadOutcome::default()
+    };
+    for worker in workers {
+        outcome.latency.merge(&worker.latency);
+        outcome.missed_deadlines += worker.missed_deadlines;
+    }
+    Ok(outcome)
+}
+
+/// Sets the workload's stop flag once its duration has passed or its energy budget is spent.
+async fn watch_bound(
+    workload: &Workload,
+    monitor: &mut dyn PowerMonitor,
+    bound: Bound,
+) -> Result<(), Box<dyn Error>> {
+    match bound {
+        // The workers stop on their own once the schedule runs out.
+        Bound::Operations => {
+            workload.finished.notified().await;
+            return Ok(());
+        }
+        Bound::Duration(duration) => {
+            let deadline = workload.start + duration;
+            while !workload.stop.load(Ordering::Relaxed) && Instant::now() < deadline {
+                sleep_until((deadline.min(Instant::now() + BOUND_POLL_INTERVAL)).into()).await;
+            }
+        }
+        Bound::EnergyBudget(joules) => {
+            while !workload.stop.load(Ordering::Relaxed) {
+                match monitor.energy_so_far() {
+                    Some(spent) if spent >= joules => break,
+                    Some(_) => sleep(BOUND_POLL_INTERVAL).await,
+                    None => {
+                        workload.stop.store(true, Ordering::Relaxed);
+                        return Err(format!(
+                            "The {} monitor can't report energy while running, so it can't enforce an energy budget",
+                            monitor.name()
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+    }
+    workload.stop.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Executes client `client`'s share of the schedule in order and records how long each operation took.
+///
+/// With open-loop pacing each operation waits for its deadline. An operation that can't start
+/// within `DEADLINE_TOLERANCE` of it, because the client's previous operation was still running,
+/// counts as a missed deadline, and its latency is measured from the deadline so queueing delay
+/// isn't hidden. Operations still unissued when the duration ends count as missed as well.
+async fn run_client(workload: &Workload, client: usize) -> Result<WorkloadOutcome, Box<dyn Error>> {
+    let mut outcome = WorkloadOutcome::default();
+    let schedule: Box<dyn Iterator<Item = Operation> + Send> = if workload.repeat {
+        Box::new(workload.mix.schedule().cycle())
+    } else {
+        Box::new(workload.mix.schedule())
+    };
+    let mut operations = schedule.enumerate().skip(client).step_by(workload.clients);
+    while let Some((index, operation)) = operations.next() {
+        if workload.stop.load(Ordering::Relaxed) {
+            if workload.pacing.is_some() {
+                outcome.missed_deadlines += 1 + operations.count() as u64;
+            }
+            break;
+        }
+        let mut started = Instant::now();
+        if let Some(pacing) = workload.pacing {
+            let deadline = workload.start + pacing.deadline(index);
+            if started > deadline + DEADLINE_TOLERANCE {
+                outcome.missed_deadlines += 1;
+                started = deadline;
+            } else if started < deadline {
+                sleep_until(deadline.into()).await;
+                started = Instant::now();
+            }
+        }
+        match operation {
+            Operation::Read => {
+                workload.db.execute_read(client).await?;
+                outcome.latency.record_read(started.elapsed());
+            }
+            Operation::Write => {
+                workload.db.execute_write(client).await?;
+                outcome.latency.record_write(started.elapsed());
+            }
+        }
+    }
+    Ok(outcome)
+}
+
+/// Samplers that run alongside the power monitor while a measurement window is open.
+struct Window {
+    processes: Option<ProcessSampler>,
+    cgroup: Option<CgroupCollector>,
+    thermal: Option<ThermalSampler>,
+}
+
+/// Opens the monitor's window, then starts the samplers, so they cover the same interval as
+/// the energy figures even when the monitor takes a while to start.
+async fn open_window(
+    monitor: &mut dyn PowerMonitor,
+    measurement_name: &str,
+    db: Option<&dyn Database>,
+) -> Result<Window, Box<dyn Error>> {
+    monitor.start(measurement_name).await?;
+    match start_samplers(db) {
+        Ok(window) => Ok(window),
+        Err(e) => {
+            let _ = monitor.stop().await;
+            Err(e)
+        }
+    }
+}
+
+fn start_samplers(db: Option<&dyn Database>) -> Result<Window, Box<dyn Error>> {
+    let processes = match db {
+        Some(db) => Some(ProcessSampler::start(
+            db.server_processes(),
+            SAMPLE_INTERVAL,
+        )?),
+        None => None,
+    };
+    let cgroup = match db.and_then(|db| db.cgroup()) {
+        Some(path) => Some(CgroupCollector::start(path, SAMPLE_INTERVAL)?),
+        None => None,
+    };
+    let thermal = ThermalSampler::start(SAMPLE_INTERVAL);
+    Ok(Window {
+        processes,
+        cgroup,
+        thermal,
+    })
+}
+
+/// Stops the samplers before the monitor, which may wait for a final sample.
+async fn close_window(
+    monitor: &mut dyn PowerMonitor,
+    window: Window,
+) -> Result<Measurement, Box<dyn Error>> {
+    let samples = stop_samplers(window).await;
+    let mut measurement = monitor.stop().await?;
+    let (thermal, cgroup, cpu) = samples?;
+
+    measurement.thermal = thermal;
+    measurement.cgroup = cgroup;
+    if let Some(cpu) = cpu {
+        measurement.attribution = measurement
+            .package_joules
+            .map(|joules| EnergyAttribution::apportion(cpu, joules));
+    }
+    Ok(measurement)
+}
+
+type WindowSamples = (
+    Option<ThermalTelemetry>,
+    Option<CgroupUsage>,
+    Option<CpuShares>,
+);
+
+async fn stop_samplers(window: Window) -> Result<WindowSamples, Box<dyn Error>> {
+    let thermal = match window.thermal {
+        Some(thermal) => Some(thermal.stop().await?),
+        None => None,
+    };
+    let cgroup = match window.cgroup {
+        Some(cgroup) => Some(cgroup.stop().await?),
+        None => None,
+    };
+    let cpu = match window.processes {
+        Some(processes) => Some(processes.stop().await?),
+        None => None,
+    };
+    Ok((thermal, cgroup, cpu))
+}
+
+pub async fn measure_idle_energy_consumption(
+    monitor: &mut dyn PowerMonitor,
     epoch: u8,
-) -> Result<u128, Box<dyn Error>> {
-    let measurement_name: String = format!(
-        "{}_write_heavy_depth_{}_epoch_{}{}.csv",
-        db.name(),
-        depth,
-        epoch,
-        if use_index { "_with_index" } else { "" }
-    );
-    
-    let read_ops = 200;
-    let write_ops = 1000;
-    
-    let start_time: Instant = Instant::now();
-    db.run_queries(depth, read_ops, write_ops, use_index, num_docs, &measurement_name)
+    idle_duration: Duration,
+    interrupt: &Interrupt,
+) -> Result<Measurement, Box<dyn Error>> {
+    let name = measurement_file_name("C1", "idle", epoch);
+    let window = open_window(monitor, &name, None).await?;
+    let aborted = tokio::select! {
+        _ = sleep(idle_duration) => false,
+        _ = interrupt.wait() => true,
+    };
+    let mut measurement = close_window(monitor, window).await?;
+    measurement.aborted = aborted;
+    Ok(measurement)
+}
+
+/// Pauses for the configured cool-down, then optionally waits until package power has
+/// dropped back to within `stabilize_within_pct` of `idle_watts`.
+///
+/// Power is read through an unrecorded monitor window, whose output file, if the monitor
+/// writes one, is deleted afterwards. When power doesn't settle before the timeout, or can't
+/// be checked at all, a warning is printed and the test runs anyway.
+pub async fn cool_down(
+    monitor: &mut dyn PowerMonitor,
+    cooldown: &Cooldown,
+    idle_watts: Option<f64>,
+    epoch: u8,
+    interrupt: &Interrupt,
+) -> Result<(), Box<dyn Error>> {
+    tokio::select! {
+        _ = sleep(cooldown.pause()) => {}
+        _ = interrupt.wait() => return Ok(()),
+    }
+
+    let Some(pct) = cooldown.stabilize_within_pct else {
+        return Ok(());
+    };
+    let Some(idle_watts) = idle_watts else {
+        eprintln!(
+            "No idle power for epoch {}, skipping the stabilization check",
+            epoch
+        );
+        return Ok(());
+    };
+    let threshold = idle_watts * (1.0 + pct / 100.0);
+
+    monitor
+        .start(&measurement_file_name("cooldown", "stabilization", epoch))
         .await?;
+    let result = tokio::select! {
+        result = wait_for_power_below(&*monitor, threshold, cooldown.stabilize_timeout()) => result,
+        _ = interrupt.wait() => None,
+    };
+    let stabilization = monitor.stop().await?;
+    if let Some(file) = stabilization.output_file {
+        if let Err(e) = fs::remove_file(&file) {
+            eprintln!("Failed to remove {}: {}", file.display(), e);
+        }
+    }
+    if interrupt.is_set() {
+        return Ok(());
+    }
 
-    let duration = start_time.elapsed().as_millis();
-    Ok(duration)
+    match result {
+        Some(Ok(waited)) => println!(
+            "Package power within {}% of idle after {}s",
+            pct,
+            waited.as_secs()
+        ),
+        Some(Err(watts)) => eprintln!(
+            "Package power still {:.1} W after {}s, above {:.1} W; measuring anyway",
+            watts, cooldown.stabilize_timeout_secs, threshold
+        ),
+        None => eprintln!(
+            "The {} monitor can't report energy while running, skipping the stabilization check",
+            monitor.name()
+        ),
+    }
+    Ok(())
 }
 
-pub async fn measure_idle_energy_consumption(epoch: u8) -> Result<u128, Box<dyn Error>> {
-    let start_time: Instant = Instant::now();
-    let name = format!("Control Test C1 epoch {}.csv", epoch);
-    let mut pcm_process = start_power_monitoring(&name);
-    sleep(Duration::from_secs(1800)).await;
-    stop_power_monitoring(&mut pcm_process);
+/// Returns how long it took for mean power over one check interval to drop to `threshold`
+/// watts, the last power seen if it didn't within `timeout`, or `None` if the monitor
+/// can't report energy while running.
+async fn wait_for_power_below(
+    monitor: &dyn PowerMonitor,
+    threshold: f64,
+    timeout: Duration,
+) -> Option<Result<Duration, f64>> {
+    let start = Instant::now();
+    let mut last = (Instant::now(), monitor.energy_so_far()?);
+    loop {
+        sleep(STABILITY_CHECK_INTERVAL).await;
+        let now = (Instant::now(), monitor.energy_so_far()?);
+        let watts = (now.1 - last.1) / (now.0 - last.0).as_secs_f64();
+        if watts <= threshold {
+            return Some(Ok(start.elapsed()));
+        }
+        if start.elapsed() >= timeout {
+            return Some(Err(watts));
+        }
+        last = now;
+    }
+}
 
-    let duration = start_time.elapsed().as_millis();
-    Ok(duration)
+/// Mutable access to a database once no workload task holds it any more.
+pub fn exclusive(
+    db: &mut Arc<dyn Database>,
+) -> Result<&mut (dyn Database + 'static), Box<dyn Error>> {
+    let name = db.name();
+    Arc::get_mut(db).ok_or_else(|| format!("{} is still in use by a workload", name).into())
 }
 
-pub async fn clean_and_insert_data(db: &mut dyn Database, depth: u8) -> Result<(), Box<dyn Error>> {
-    let test_data = load_test_data(depth);
+pub async fn clean_and_insert_data(
+    db: &mut dyn Database,
+    dataset: &Dataset,
+    batch_size: usize,
+) -> Result<(), Box<dyn Error>> {
+    let test_data = load_test_data(&dataset.path())?;
     db.connect().await?;
     db.clean_database().await?;
-    db.insert_test_data(1000, test_data).await?;
+    db.insert_test_data(batch_size, test_data).await?;
     Ok(())
 }
 
-fn load_test_data(depth: u8) -> Vec<Value> {
-    let data = fs::read_to_string(format!("src/test_data/data_depth_{}.json", depth)).unwrap();
-    serde_json::from_str(&data).unwrap()
+fn load_test_data(path: &Path) -> Result<Vec<Value>, Box<dyn Error>> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read test data {}: {}", path.display(), e))?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::simulated::SimulatedMonitor;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Counts the operations run against it. Every operation yields once, like a real
+    /// round trip would, then takes `latency`.
+    #[derive(Default)]
+    struct CountingDatabase {
+        reads: AtomicUsize,
+        writes: AtomicUsize,
+        latency: Duration,
+    }
+
+    impl CountingDatabase {
+        async fn round_trip(&self) {
+            tokio::task::yield_now().await;
+            if !self.latency.is_zero() {
+                sleep(self.latency).await;
+            }
+        }
+
+        fn operations(&self) -> usize {
+            self.reads.load(Ordering::Relaxed) + self.writes.load(Ordering::Relaxed)
+        }
+    }
+
+    #[async_trait]
+    impl Database for CountingDatabase {
+        fn name(&self) -> &'static str {
+            "Counting"
+        }
+
+        fn server_processes(&self) -> &'static [&'static str] {
+            &[]
+        }
+
+        fn cgroup(&self) -> Option<&Path> {
+            None
+        }
+
+        async fn connect(&mut self) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn server_version(&self) -> Result<String, Box<dyn Error>> {
+            Ok("1.0".to_string())
+        }
+
+        async fn clean_database(&self) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn insert_test_data(
+            &self,
+            _batch_size: usize,
+            _test_data: Vec<Value>,
+        ) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn prepare_indexes(&self, _depth: u8) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn prepare_workload(
+            &mut self,
+            _depth: u8,
+            _num_docs: usize,
+            _clients: usize,
+        ) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn execute_read(&self, _client: usize) -> Result<(), Box<dyn Error>> {
+            self.round_trip().await;
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        async fn execute_write(&self, _client: usize) -> Result<(), Box<dyn Error>> {
+            self.round_trip().await;
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    fn test_case(fields: Value) -> TestCase {
+        let mut test = json!({ "id": "T", "name": "test", "read_ratio": 5, "write_ratio": 1 });
+        test.as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(test).unwrap()
+    }
+
+    async fn run(test: &TestCase) -> (Arc<CountingDatabase>, WorkloadOutcome) {
+        run_with_latency(test, Duration::ZERO).await
+    }
+
+    async fn run_with_latency(
+        test: &TestCase,
+        latency: Duration,
+    ) -> (Arc<CountingDatabase>, WorkloadOutcome) {
+        let db = Arc::new(CountingDatabase {
+            latency,
+            ..CountingDatabase::default()
+        });
+        let (_sender, receiver) = watch::channel(false);
+        let interrupt = Interrupt { receiver };
+        let mut monitor = SimulatedMonitor::fixed(50.0);
+        let outcome = run_workload(db.clone(), &mut monitor, test, &interrupt)
+            .await
+            .unwrap();
+        (db, outcome)
+    }
+
+    #[tokio::test]
+    async fn operation_bound_runs_the_whole_schedule() {
+        let (db, outcome) = run(&test_case(json!({ "operations": 120 }))).await;
+        assert_eq!(db.reads.load(Ordering::Relaxed), 100);
+        assert_eq!(db.writes.load(Ordering::Relaxed), 20);
+        assert_eq!(outcome.latency.reads.len(), 100);
+        assert_eq!(outcome.latency.writes.len(), 20);
+        assert!(!outcome.interrupted);
+    }
+
+    #[tokio::test]
+    async fn operation_bound_splits_the_schedule_between_clients() {
+        let (db, outcome) = run(&test_case(json!({ "operations": 120, "clients": 4 }))).await;
+        assert_eq!(db.reads.load(Ordering::Relaxed), 100);
+        assert_eq!(db.writes.load(Ordering::Relaxed), 20);
+        assert_eq!(
+            outcome.latency.reads.len() + outcome.latency.writes.len(),
+            120
+        );
+    }
+
+    #[tokio::test]
+    async fn duration_bound_repeats_the_schedule_until_it_expires() {
+        let started = Instant::now();
+        let (db, _) = run(&test_case(json!({ "duration_secs": 1, "clients": 2 }))).await;
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert!(db.reads.load(Ordering::Relaxed) > 5);
+        assert!(db.writes.load(Ordering::Relaxed) > 1);
+    }
+
+    #[tokio::test]
+    async fn open_loop_issues_the_whole_schedule_when_the_database_keeps_up() {
+        let test = test_case(json!({ "target_rate": 100.0, "duration_secs": 1 }));
+        let (db, _) = run(&test).await;
+        assert_eq!(db.operations(), 100);
+    }
+
+    #[tokio::test]
+    async fn open_loop_stops_a_slow_database_on_time_and_counts_what_it_missed() {
+        let test = test_case(json!({ "target_rate": 100.0, "duration_secs": 1 }));
+        let started = Instant::now();
+        let (db, outcome) = run_with_latency(&test, Duration::from_millis(50)).await;
+
+        // Draining the whole schedule would take 100 × 50 ms.
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(db.operations() < 100);
+        // At most the first operation started on time; the rest started late or never.
+        assert!((99..=100).contains(&outcome.missed_deadlines));
+    }
+
+    /// Writes an empty output file per window, like PCM, and can't report energy while running.
+    struct FileMonitor {
+        dir: std::path::PathBuf,
+        file: Option<std::path::PathBuf>,
+    }
+
+    #[async_trait]
+    impl PowerMonitor for FileMonitor {
+        fn name(&self) -> &'static str {
+            "file"
+        }
+
+        async fn start(&mut self, measurement_name: &str) -> Result<(), Box<dyn Error>> {
+            let file = self.dir.join(measurement_name);
+            fs::write(&file, "")?;
+            self.file = Some(file);
+            Ok(())
+        }
+
+        async fn stop(&mut self) -> Result<Measurement, Box<dyn Error>> {
+            let mut measurement = Measurement::new(
+                "window".to_string(),
+                self.name(),
+                std::time::SystemTime::now(),
+                Duration::ZERO,
+            );
+            measurement.output_file = self.file.take();
+            Ok(measurement)
+        }
+    }
+
+    #[tokio::test]
+    async fn cool_down_leaves_no_stabilization_file_behind() {
+        let dir = std::env::temp_dir().join(format!("cooldown_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut monitor = FileMonitor {
+            dir: dir.clone(),
+            file: None,
+        };
+        let cooldown: Cooldown =
+            serde_json::from_value(json!({ "stabilize_within_pct": 10 })).unwrap();
+        let (_sender, receiver) = watch::channel(false);
+        let interrupt = Interrupt { receiver };
+
+        cool_down(&mut monitor, &cooldown, Some(20.0), 1, &interrupt)
+            .await
+            .unwrap();
+        let left = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(left, 0);
+    }
 }
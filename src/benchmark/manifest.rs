@@ -0,0 +1,208 @@
+use crate::benchmark::plan::BenchmarkPlan;
+use crate::metrics::{current_governors, pcm_version};
+use fs_extra::dir::create_all;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use sysinfo::{CpuExt, CpuRefreshKind, RefreshKind, System, SystemExt};
+
+/// File the run manifest is written to, inside the results directory. Every resume of the
+/// run writes its own `manifest_resume_<n>.json` next to it.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Everything needed to tell how a run's results were produced: the plan, the build, the
+/// machine, the software measured and the exact input data.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunManifest {
+    #[serde(skip)]
+    path: PathBuf,
+    pub crate_version: String,
+    /// Commit the binary was built from; `None` when it wasn't built from a git checkout.
+    pub git_commit: Option<String>,
+    /// Whether the checkout had uncommitted changes when the binary was built.
+    pub git_dirty: Option<bool>,
+    pub started_at_ms: u128,
+    /// Set once the run has finished, failed or was interrupted.
+    pub ended_at_ms: Option<u128>,
+    /// Which resume of the run this manifest covers; `None` for the run's first start.
+    pub resume: Option<u32>,
+    pub interrupted: bool,
+    /// Why the run stopped early, if it failed.
+    pub error: Option<String>,
+    pub order_seed: Option<u64>,
+    pub system: SystemInfo,
+    pub pcm_version: Option<String>,
+    pub databases: Vec<DatabaseVersion>,
+    pub datasets: Vec<DatasetChecksum>,
+    pub plan: BenchmarkPlan,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemInfo {
+    pub cpu_model: String,
+    pub physical_cores: Option<usize>,
+    pub logical_cores: usize,
+    pub kernel_version: Option<String>,
+    pub total_memory_bytes: u64,
+    /// Distinct cpufreq governors active across all CPUs.
+    pub governors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseVersion {
+    pub database: String,
+    pub version: Option<String>,
+    /// Why the version couldn't be read, e.g. the server wasn't reachable.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetChecksum {
+    pub depth: u8,
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+impl RunManifest {
+    /// Gathers the manifest at the start of a run in `run_dir`. Each database is connected
+    /// briefly to ask for its version. A resumed run gets a manifest of its own, so the
+    /// first start's environment and timestamps are kept.
+    pub async fn collect(
+        plan: &BenchmarkPlan,
+        order_seed: Option<u64>,
+        run_dir: &Path,
+        resumed: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let resume = resumed.then(|| next_resume(run_dir));
+        let file_name = match resume {
+            Some(n) => resume_file_name(n),
+            None => MANIFEST_FILE_NAME.to_string(),
+        };
+        Ok(RunManifest {
+            path: run_dir.join(file_name),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: build_env(env!("GIT_COMMIT")).map(str::to_string),
+            git_dirty: build_env(env!("GIT_DIRTY")).map(|dirty| dirty == "true"),
+            started_at_ms: unix_millis_now(),
+            ended_at_ms: None,
+            resume,
+            interrupted: false,
+            error: None,
+            order_seed,
+            system: SystemInfo::collect(),
+            pcm_version: pcm_version(),
+            databases: database_versions(plan).await?,
+            datasets: plan
+                .datasets
+                .iter()
+                .map(|dataset| DatasetChecksum::compute(dataset.depth, &dataset.path()))
+                .collect::<Result<_, _>>()?,
+            plan: plan.clone(),
+        })
+    }
+
+    pub fn finish(&mut self, interrupted: bool, error: Option<String>) {
+        self.ended_at_ms = Some(unix_millis_now());
+        self.interrupted = interrupted;
+        self.error = error;
+    }
+
+    pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent() {
+            create_all(dir, false)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// The first resume number without a manifest in `run_dir`.
+fn next_resume(run_dir: &Path) -> u32 {
+    let mut resume = 1;
+    while run_dir.join(resume_file_name(resume)).exists() {
+        resume += 1;
+    }
+    resume
+}
+
+fn resume_file_name(resume: u32) -> String {
+    format!("manifest_resume_{}.json", resume)
+}
+
+impl SystemInfo {
+    fn collect() -> Self {
+        let system = System::new_with_specifics(
+            RefreshKind::new()
+                .with_cpu(CpuRefreshKind::new())
+                .with_memory(),
+        );
+        SystemInfo {
+            cpu_model: system
+                .cpus()
+                .first()
+                .map(|cpu| cpu.brand().trim().to_string())
+                .unwrap_or_default(),
+            physical_cores: system.physical_core_count(),
+            logical_cores: system.cpus().len(),
+            kernel_version: system.kernel_version(),
+            total_memory_bytes: system.total_memory(),
+            governors: current_governors(),
+        }
+    }
+}
+
+impl DatasetChecksum {
+    fn compute(depth: u8, path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read(path)
+            .map_err(|e| format!("Failed to read dataset {}: {}", path.display(), e))?;
+        let digest = Sha256::digest(&content);
+        Ok(DatasetChecksum {
+            depth,
+            path: path.to_path_buf(),
+            bytes: content.len() as u64,
+            sha256: digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        })
+    }
+}
+
+async fn database_versions(plan: &BenchmarkPlan) -> Result<Vec<DatabaseVersion>, Box<dyn Error>> {
+    let mut versions = Vec::new();
+    for mut db in plan.create_databases()? {
+        let version = match db.connect().await {
+            Ok(()) => db.server_version().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = db.disconnect().await {
+            eprintln!("Failed to disconnect from {}: {}", db.name(), e);
+        }
+        versions.push(match version {
+            Ok(version) => DatabaseVersion {
+                database: db.name().to_string(),
+                version: Some(version),
+                error: None,
+            },
+            Err(e) => DatabaseVersion {
+                database: db.name().to_string(),
+                version: None,
+                error: Some(e.to_string()),
+            },
+        });
+    }
+    Ok(versions)
+}
+
+/// A value recorded by `build.rs`, which leaves it empty when it couldn't be determined.
+fn build_env(value: &'static str) -> Option<&'static str> {
+    (!value.is_empty()).then_some(value)
+}
+
+fn unix_millis_now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0)
+}
@@ -1,2 +1,10 @@
+pub mod analysis;
+pub mod checkpoint;
 pub mod data_generator;
 pub mod harness;
+pub mod manifest;
+pub mod output;
+pub mod plan;
+pub mod results;
+pub mod runner;
+pub mod workload;
@@ -0,0 +1,68 @@
+use crate::benchmark::checkpoint::STATE_FILE_NAME;
+use chrono::Utc;
+use fs_extra::dir::create_all;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Results root used when `--results` isn't given, relative to the working directory.
+pub const DEFAULT_RESULTS_ROOT: &str = "results";
+
+/// File listing every measurement window of a run, inside its run directory.
+pub const INDEX_FILE_NAME: &str = "index.csv";
+
+/// Run directories are named after the UTC time the run started, so they sort chronologically.
+const RUN_DIR_FORMAT: &str = "run_%Y-%m-%dT%H-%M-%SZ";
+
+/// Creates a new, empty directory for one run under `root`.
+pub fn create_run_dir(root: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    create_all(root, false)?;
+    let dir = root.join(Utc::now().format(RUN_DIR_FORMAT).to_string());
+    fs::create_dir(&dir)
+        .map_err(|e| format!("Failed to create run directory {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Every run directory under `root`, oldest first.
+pub fn run_dirs(root: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(root)
+        .map_err(|e| format!("Failed to read results root {}: {}", root.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("run_"))
+        })
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// The most recent run under `root` that recorded its progress, for `--resume`.
+pub fn latest_resumable_run(root: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    run_dirs(root)?
+        .into_iter()
+        .rev()
+        .find(|dir| dir.join(STATE_FILE_NAME).is_file())
+        .ok_or_else(|| format!("No run to resume under {}", root.display()).into())
+}
+
+/// Name of the monitor output of one measurement window: `<test id>_<subject>_epoch_<n>.csv`,
+/// e.g. `1A_MongoDB_depth_3_epoch_1.csv` or `C1_idle_epoch_1.csv`.
+pub fn measurement_file_name(test_id: &str, subject: &str, epoch: u8) -> String {
+    let name = format!("{}_{}_epoch_{}", test_id, subject, epoch);
+    // Plan ids and names end up in file names; keep them free of spaces and separators.
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.csv", name)
+}
@@ -0,0 +1,400 @@
+use crate::benchmark::workload::{Bound, OpMix, OpenLoop};
+use crate::databases::database::{create_database, Database, DATABASE_NAMES};
+use crate::metrics::{DriftThresholds, MONITOR_NAMES};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A full experiment: which databases, datasets and test cases to run, and how often.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BenchmarkPlan {
+    #[serde(default = "default_monitor")]
+    pub monitor: String,
+    pub epochs: Vec<u8>,
+    pub idle_duration_secs: u64,
+    pub num_docs: usize,
+    #[serde(default = "default_insert_batch_size")]
+    pub insert_batch_size: usize,
+    /// Extra attempts for a test case that fails, each after reconnecting and reloading its data.
+    #[serde(default)]
+    pub retries: u32,
+    /// Pause and power check before every measured test case.
+    #[serde(default)]
+    pub cooldown: Cooldown,
+    /// Order of the database × depth × test case cells within each epoch.
+    #[serde(default)]
+    pub order: TestOrder,
+    /// Seed for `shuffled` order; a random one is drawn and recorded when omitted.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Temperature and frequency drift within a window above which a run is flagged.
+    #[serde(default)]
+    pub drift: DriftThresholds,
+    pub databases: Vec<DatabaseConfig>,
+    pub datasets: Vec<Dataset>,
+    pub test_cases: Vec<TestCase>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestOrder {
+    /// Databases, then depths, then test cases, as listed in the plan.
+    #[default]
+    Sequential,
+    /// A different random permutation of the cells in every epoch.
+    Shuffled,
+    /// The sequential order, reversed on every other epoch.
+    Counterbalanced,
+}
+
+/// How the harness lets the machine settle after a data load or test before the next window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Cooldown {
+    /// Fixed pause before every measured test case.
+    #[serde(default)]
+    pub secs: u64,
+    /// After the pause, wait until package power is at most this many percent above the
+    /// epoch's C1 idle power.
+    #[serde(default)]
+    pub stabilize_within_pct: Option<f64>,
+    /// Give up waiting for power to stabilize after this long and measure anyway.
+    #[serde(default = "default_stabilize_timeout_secs")]
+    pub stabilize_timeout_secs: u64,
+}
+
+impl Default for Cooldown {
+    fn default() -> Self {
+        Cooldown {
+            secs: 0,
+            stabilize_within_pct: None,
+            stabilize_timeout_secs: default_stabilize_timeout_secs(),
+        }
+    }
+}
+
+impl Cooldown {
+    pub fn pause(&self) -> Duration {
+        Duration::from_secs(self.secs)
+    }
+
+    pub fn stabilize_timeout(&self) -> Duration {
+        Duration::from_secs(self.stabilize_timeout_secs)
+    }
+}
+
+/// Selection applied on top of a plan; empty lists select everything.
+#[derive(Debug, Clone, Default)]
+pub struct PlanFilter {
+    pub databases: Vec<String>,
+    pub test_ids: Vec<String>,
+    pub depths: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub name: String,
+    /// cgroup v2 the server runs in, absolute or relative to `/sys/fs/cgroup`.
+    #[serde(default)]
+    pub cgroup: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dataset {
+    pub depth: u8,
+    /// Defaults to `src/test_data/data_depth_<depth>.json`.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+impl Dataset {
+    pub fn path(&self) -> PathBuf {
+        self.path.clone().unwrap_or_else(|| {
+            PathBuf::from(format!("src/test_data/data_depth_{}.json", self.depth))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestCase {
+    pub id: String,
+    /// Short descriptive name shown by `list`, e.g. `read_heavy`. Files are named by `id`.
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub read_ratio: u32,
+    pub write_ratio: u32,
+    /// Operations run back to back; open-loop test cases derive their count from the rate instead.
+    #[serde(default)]
+    pub operations: usize,
+    /// Open-loop mode: issue operations at this many per second for `duration_secs`.
+    #[serde(default)]
+    pub target_rate: Option<f64>,
+    /// Without `target_rate`, run operations back to back for this long instead of a fixed count.
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+    /// Run operations back to back until the power monitor has measured this many package joules.
+    #[serde(default)]
+    pub energy_budget_joules: Option<f64>,
+    /// Independent connections driving the workload concurrently.
+    #[serde(default = "default_clients")]
+    pub clients: usize,
+    #[serde(default)]
+    pub use_index: bool,
+    /// Drop and reload the dataset before this test case runs. The dataset is also
+    /// reloaded whenever the database holds another depth or indexes this test doesn't use.
+    #[serde(default)]
+    pub reload_data: bool,
+}
+
+impl TestCase {
+    /// Splits the test's operations into reads and writes according to the ratio. Open-loop
+    /// test cases schedule every operation due within their duration; other time- and
+    /// energy-bounded test cases get one period of the ratio, repeated until the bound is hit.
+    pub fn op_mix(&self) -> OpMix {
+        let operations = match (self.open_loop(), self.bound()) {
+            (Some(pacing), _) => pacing.operations(),
+            (None, Bound::Operations) => self.operations,
+            _ => return OpMix::period(self.read_ratio, self.write_ratio),
+        };
+        OpMix::from_ratio(self.read_ratio, self.write_ratio, operations)
+    }
+
+    /// Open-loop test cases are bounded by their duration too, so a backend that falls behind
+    /// the schedule is measured over the same window as one that keeps up.
+    pub fn bound(&self) -> Bound {
+        match (self.energy_budget_joules, self.duration_secs) {
+            (Some(joules), _) => Bound::EnergyBudget(joules),
+            (None, Some(secs)) => Bound::Duration(Duration::from_secs(secs)),
+            (None, None) => Bound::Operations,
+        }
+    }
+
+    pub fn open_loop(&self) -> Option<OpenLoop> {
+        Some(OpenLoop {
+            rate: self.target_rate?,
+            duration: Duration::from_secs(self.duration_secs?),
+        })
+    }
+}
+
+fn default_monitor() -> String {
+    "pcm".to_string()
+}
+
+fn default_clients() -> usize {
+    1
+}
+
+fn default_insert_batch_size() -> usize {
+    1000
+}
+
+fn default_stabilize_timeout_secs() -> u64 {
+    300
+}
+
+impl BenchmarkPlan {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let plan = Self::read(path)?;
+        plan.validate()
+            .map_err(|e| format!("Invalid plan {}: {}", path.display(), e))?;
+        Ok(plan)
+    }
+
+    /// Parses a plan without validating it, e.g. to narrow it down with a filter first.
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read plan {}: {}", path.display(), e))?;
+        let plan = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid plan {}: {}", path.display(), e))?;
+        Ok(plan)
+    }
+
+    /// Checks everything that can be checked before any database is touched,
+    /// reporting all problems at once.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let mut problems = Vec::new();
+
+        if !MONITOR_NAMES.contains(&self.monitor.as_str()) {
+            problems.push(format!(
+                "unknown monitor '{}' (expected one of {})",
+                self.monitor,
+                MONITOR_NAMES.join(", ")
+            ));
+        }
+        if self.epochs.is_empty() {
+            problems.push("at least one epoch is required".to_string());
+        }
+        if has_duplicates(self.epochs.iter()) {
+            problems.push("epochs must be unique".to_string());
+        }
+        if self.num_docs == 0 {
+            problems.push("num_docs must be greater than 0".to_string());
+        }
+        if self.insert_batch_size == 0 {
+            problems.push("insert_batch_size must be greater than 0".to_string());
+        }
+
+        if let Some(pct) = self.cooldown.stabilize_within_pct {
+            if !(pct > 0.0 && pct.is_finite()) {
+                problems.push("cooldown.stabilize_within_pct must be positive".to_string());
+            }
+            if self.cooldown.stabilize_timeout_secs == 0 {
+                problems.push("cooldown.stabilize_timeout_secs must be greater than 0".to_string());
+            }
+        }
+        if !(self.drift.max_temp_drift_c > 0.0 && self.drift.max_temp_drift_c.is_finite()) {
+            problems.push("drift.max_temp_drift_c must be positive".to_string());
+        }
+        if !(self.drift.max_freq_drift_pct > 0.0 && self.drift.max_freq_drift_pct.is_finite()) {
+            problems.push("drift.max_freq_drift_pct must be positive".to_string());
+        }
+        if self.seed.is_some() && self.order != TestOrder::Shuffled {
+            problems.push("seed is only used with the shuffled order".to_string());
+        }
+
+        if self.databases.is_empty() {
+            problems.push("at least one database is required".to_string());
+        }
+        for db in &self.databases {
+            if !DATABASE_NAMES.contains(&db.name.as_str()) {
+                problems.push(format!(
+                    "unknown database '{}' (expected one of {})",
+                    db.name,
+                    DATABASE_NAMES.join(", ")
+                ));
+            }
+        }
+        if has_duplicates(self.databases.iter().map(|db| &db.name)) {
+            problems.push("databases must be unique".to_string());
+        }
+
+        if self.datasets.is_empty() {
+            problems.push("at least one dataset is required".to_string());
+        }
+        for dataset in &self.datasets {
+            if dataset.depth == 0 {
+                problems.push("dataset depth must be at least 1".to_string());
+            } else if !dataset.path().exists() {
+                problems.push(format!(
+                    "dataset for depth {} not found at {}",
+                    dataset.depth,
+                    dataset.path().display()
+                ));
+            }
+        }
+        // Datasets are told apart by depth in checkpoints, file names and reload decisions.
+        if has_duplicates(self.datasets.iter().map(|dataset| dataset.depth)) {
+            problems.push("dataset depths must be unique".to_string());
+        }
+
+        if self.test_cases.is_empty() {
+            problems.push("at least one test case is required".to_string());
+        }
+        if has_duplicates(self.test_cases.iter().map(|test| &test.id)) {
+            problems.push("test case ids must be unique".to_string());
+        }
+        for test in &self.test_cases {
+            if test.read_ratio == 0 && test.write_ratio == 0 {
+                problems.push(format!(
+                    "test case {} needs a non-zero read or write ratio",
+                    test.id
+                ));
+            }
+            let counted = test.operations > 0;
+            match (
+                counted,
+                test.target_rate,
+                test.duration_secs,
+                test.energy_budget_joules,
+            ) {
+                (true, None, None, None) => {}
+                (false, None, None, None) => {
+                    problems.push(format!("test case {} has no operations", test.id));
+                }
+                (false, Some(rate), Some(secs), None) => {
+                    if !(rate > 0.0 && rate.is_finite()) || secs == 0 {
+                        problems.push(format!(
+                            "test case {} needs a positive target_rate and duration_secs",
+                            test.id
+                        ));
+                    } else if test.op_mix().total() == 0 {
+                        problems.push(format!("test case {} has no operations", test.id));
+                    }
+                }
+                (false, None, Some(secs), None) => {
+                    if secs == 0 {
+                        problems.push(format!(
+                            "test case {} needs a positive duration_secs",
+                            test.id
+                        ));
+                    }
+                }
+                (false, None, None, Some(joules)) => {
+                    if !(joules > 0.0 && joules.is_finite()) {
+                        problems.push(format!(
+                            "test case {} needs a positive energy_budget_joules",
+                            test.id
+                        ));
+                    }
+                }
+                _ => problems.push(format!(
+                    "test case {} must set exactly one of operations, target_rate with \
+                     duration_secs, duration_secs or energy_budget_joules",
+                    test.id
+                )),
+            }
+            if test.clients == 0 {
+                problems.push(format!("test case {} needs at least one client", test.id));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("; ").into())
+        }
+    }
+
+    pub fn idle_duration(&self) -> Duration {
+        Duration::from_secs(self.idle_duration_secs)
+    }
+
+    /// Narrows the plan to the databases, test cases and depths selected by `filter`.
+    pub fn filtered(&self, filter: &PlanFilter) -> Result<Self, Box<dyn Error>> {
+        let mut plan = self.clone();
+        plan.databases
+            .retain(|db| filter.databases.is_empty() || filter.databases.contains(&db.name));
+        plan.test_cases
+            .retain(|test| filter.test_ids.is_empty() || filter.test_ids.contains(&test.id));
+        plan.datasets
+            .retain(|dataset| filter.depths.is_empty() || filter.depths.contains(&dataset.depth));
+
+        if plan.databases.is_empty() || plan.test_cases.is_empty() || plan.datasets.is_empty() {
+            return Err(
+                "Filter does not match any database, test case and depth of the plan".into(),
+            );
+        }
+        Ok(plan)
+    }
+
+    pub fn create_databases(&self) -> Result<Vec<Box<dyn Database>>, Box<dyn Error>> {
+        self.databases
+            .iter()
+            .map(|db| create_database(&db.name, db.cgroup.clone()))
+            .collect()
+    }
+}
+
+fn has_duplicates<T: Eq + std::hash::Hash>(items: impl Iterator<Item = T>) -> bool {
+    let mut seen = HashSet::new();
+    items.into_iter().any(|item| !seen.insert(item))
+}
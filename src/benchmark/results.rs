@@ -0,0 +1,543 @@
+use crate::benchmark::plan::TestCase;
+use crate::metrics::{DriftThresholds, LatencySummary, Measurement, OperationLatencies};
+use fs_extra::dir::create_all;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone)]
+pub struct TestRun {
+    pub test_id: String,
+    pub database: String,
+    pub depth: u8,
+    pub epoch: u8,
+    /// Position of the run within its epoch, starting at 1.
+    pub sequence: usize,
+    pub stop_condition: &'static str,
+    /// Operations completed inside the measurement window.
+    pub operations: usize,
+    pub clients: usize,
+    /// Open-loop target rate in operations per second.
+    pub target_rate: Option<f64>,
+    pub measurement: Measurement,
+}
+
+/// One line of the results summary, with energy above the epoch's idle baseline.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub test_id: String,
+    pub database: String,
+    pub depth: u8,
+    pub epoch: u8,
+    pub sequence: usize,
+    /// Seed the test order was shuffled with, if it was.
+    pub order_seed: Option<u64>,
+    pub clients: usize,
+    pub stop_condition: &'static str,
+    pub operations: usize,
+    pub target_rate: Option<f64>,
+    pub achieved_ops_per_sec: Option<f64>,
+    pub missed_deadlines: Option<u64>,
+    /// Set when an interrupt cut the window short.
+    pub aborted: bool,
+    /// Effective window boundaries, in milliseconds since the Unix epoch.
+    pub window_start_ms: u128,
+    pub window_end_ms: u128,
+    pub duration_ms: u128,
+    pub gross_joules: Option<f64>,
+    pub idle_watts: Option<f64>,
+    pub net_joules: Option<f64>,
+    pub net_joules_per_op: Option<f64>,
+    pub ops_per_net_joule: Option<f64>,
+    pub server_joules: Option<f64>,
+    pub client_joules: Option<f64>,
+    pub other_joules: Option<f64>,
+    pub cgroup_cpu_usec: Option<u64>,
+    pub cgroup_read_bytes: Option<u64>,
+    pub cgroup_written_bytes: Option<u64>,
+    pub cgroup_peak_memory_bytes: Option<u64>,
+    pub max_temp_c: Option<f64>,
+    pub temp_drift_c: Option<f64>,
+    pub mean_freq_mhz: Option<f64>,
+    pub freq_drift_pct: Option<f64>,
+    pub governors: String,
+    /// Set when temperature or frequency moved beyond the drift thresholds during the run.
+    pub thermal_drift: bool,
+}
+
+/// Client (benchmark process) versus database server energy for one test run.
+///
+/// Energy is split by CPU share; `energy_basis` is `net` when an idle baseline was
+/// available for the epoch and `gross` otherwise.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientServerSplit {
+    pub test_id: String,
+    pub database: String,
+    pub depth: u8,
+    pub epoch: u8,
+    pub energy_basis: &'static str,
+    pub client_cpu_secs: f64,
+    pub server_cpu_secs: f64,
+    pub client_joules: f64,
+    pub server_joules: f64,
+    pub client_share: f64,
+}
+
+/// Latency percentiles of one operation type for a test, backend and depth, over all epochs.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyReport {
+    pub test_id: String,
+    pub database: String,
+    pub depth: u8,
+    pub operation: &'static str,
+    pub epochs: usize,
+    pub count: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub p999_us: u64,
+    pub max_us: u64,
+}
+
+/// A test run that still failed after its last attempt.
+#[derive(Debug, Clone, Serialize)]
+pub struct Failure {
+    pub test_id: String,
+    pub database: String,
+    pub depth: u8,
+    pub epoch: u8,
+    pub attempts: u32,
+    pub error: String,
+}
+
+/// One measurement window of a run, C1 controls included, as listed in the run's index.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexEntry {
+    pub epoch: u8,
+    /// Position of the test run within its epoch; empty for the C1 control.
+    pub sequence: Option<usize>,
+    pub test_id: String,
+    pub database: Option<String>,
+    pub depth: Option<u8>,
+    pub monitor: String,
+    /// Name of the monitor's output next to the index, for monitors that write one.
+    pub file: Option<String>,
+    pub window_start_ms: u128,
+    pub window_end_ms: u128,
+    pub duration_ms: u128,
+    pub package_joules: Option<f64>,
+    pub aborted: bool,
+}
+
+/// Collects the C1 idle controls and every measured test of a suite.
+#[derive(Debug, Default)]
+pub struct Results {
+    idle_baselines: HashMap<u8, Measurement>,
+    runs: Vec<TestRun>,
+    failures: Vec<Failure>,
+    drift_thresholds: DriftThresholds,
+    order_seed: Option<u64>,
+}
+
+impl Results {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_drift_thresholds(mut self, thresholds: DriftThresholds) -> Self {
+        self.drift_thresholds = thresholds;
+        self
+    }
+
+    pub fn with_order_seed(mut self, seed: Option<u64>) -> Self {
+        self.order_seed = seed;
+        self
+    }
+
+    pub fn order_seed(&self) -> Option<u64> {
+        self.order_seed
+    }
+
+    pub fn record_idle(&mut self, epoch: u8, measurement: Measurement) {
+        self.idle_baselines.insert(epoch, measurement);
+    }
+
+    pub fn record_run(
+        &mut self,
+        test: &TestCase,
+        database: &str,
+        depth: u8,
+        epoch: u8,
+        measurement: Measurement,
+    ) {
+        let sequence = self.runs.iter().filter(|run| run.epoch == epoch).count() + 1;
+        self.runs.push(TestRun {
+            test_id: test.id.clone(),
+            database: database.to_string(),
+            depth,
+            epoch,
+            sequence,
+            stop_condition: test.bound().label(),
+            // Time- and energy-bounded runs only know their operation count afterwards.
+            operations: measurement
+                .latency
+                .as_ref()
+                .map(|latency| (latency.reads.len() + latency.writes.len()) as usize)
+                .unwrap_or_else(|| test.op_mix().total()),
+            clients: test.clients,
+            target_rate: test.open_loop().map(|pacing| pacing.rate),
+            measurement,
+        });
+    }
+
+    pub fn record_failure(&mut self, failure: Failure) {
+        self.failures.push(failure);
+    }
+
+    pub fn failures(&self) -> &[Failure] {
+        &self.failures
+    }
+
+    pub fn runs(&self) -> &[TestRun] {
+        &self.runs
+    }
+
+    /// Mean idle power of the C1 control recorded in `epoch`.
+    pub fn idle_watts(&self, epoch: u8) -> Option<f64> {
+        self.idle_baselines
+            .get(&epoch)
+            .and_then(Measurement::mean_watts)
+    }
+
+    pub fn summarize(&self, run: &TestRun) -> RunSummary {
+        let gross_joules = run.measurement.package_joules;
+        let attribution = run.measurement.attribution;
+        let cgroup = run.measurement.cgroup;
+        let thermal = run.measurement.thermal.as_ref();
+        let idle_watts = self.idle_watts(run.epoch);
+        let duration_secs = run.measurement.duration.as_secs_f64();
+        let net_joules = match (gross_joules, idle_watts) {
+            (Some(gross), Some(idle)) => Some(gross - idle * duration_secs),
+            _ => None,
+        };
+
+        RunSummary {
+            test_id: run.test_id.clone(),
+            database: run.database.clone(),
+            depth: run.depth,
+            epoch: run.epoch,
+            sequence: run.sequence,
+            order_seed: self.order_seed,
+            clients: run.clients,
+            stop_condition: run.stop_condition,
+            operations: run.operations,
+            target_rate: run.target_rate,
+            achieved_ops_per_sec: (duration_secs > 0.0)
+                .then(|| run.operations as f64 / duration_secs),
+            missed_deadlines: run.measurement.missed_deadlines,
+            aborted: run.measurement.aborted,
+            window_start_ms: unix_millis(run.measurement.started_at),
+            window_end_ms: unix_millis(run.measurement.ended_at),
+            duration_ms: run.measurement.duration.as_millis(),
+            gross_joules,
+            idle_watts,
+            net_joules,
+            net_joules_per_op: net_joules
+                .filter(|_| run.operations > 0)
+                .map(|net| net / run.operations as f64),
+            ops_per_net_joule: net_joules
+                .filter(|&net| net > 0.0)
+                .map(|net| run.operations as f64 / net),
+            server_joules: attribution.map(|a| a.server_joules),
+            client_joules: attribution.map(|a| a.client_joules),
+            other_joules: attribution.map(|a| a.other_joules),
+            cgroup_cpu_usec: cgroup.map(|c| c.cpu_usec),
+            cgroup_read_bytes: cgroup.map(|c| c.read_bytes),
+            cgroup_written_bytes: cgroup.map(|c| c.written_bytes),
+            cgroup_peak_memory_bytes: cgroup.map(|c| c.peak_memory_bytes),
+            max_temp_c: thermal.and_then(|t| t.max_temp_c),
+            temp_drift_c: thermal.and_then(|t| t.temp_drift_c()),
+            mean_freq_mhz: thermal.and_then(|t| t.mean_freq_mhz),
+            freq_drift_pct: thermal.and_then(|t| t.freq_drift_pct()),
+            governors: thermal.map(|t| t.governors.join(" ")).unwrap_or_default(),
+            thermal_drift: thermal.is_some_and(|t| t.drifted(&self.drift_thresholds)),
+        }
+    }
+
+    pub fn summaries(&self) -> Vec<RunSummary> {
+        self.runs.iter().map(|run| self.summarize(run)).collect()
+    }
+
+    /// Every measurement window, epoch by epoch, with the C1 control first and the test
+    /// runs in the order they ran.
+    pub fn index(&self) -> Vec<IndexEntry> {
+        let mut epochs: Vec<u8> = self.idle_baselines.keys().copied().collect();
+        epochs.extend(self.runs.iter().map(|run| run.epoch));
+        epochs.sort_unstable();
+        epochs.dedup();
+
+        let mut entries = Vec::new();
+        for epoch in epochs {
+            if let Some(idle) = self.idle_baselines.get(&epoch) {
+                entries.push(index_entry(epoch, None, "C1", None, None, idle));
+            }
+            let mut runs: Vec<&TestRun> =
+                self.runs.iter().filter(|run| run.epoch == epoch).collect();
+            runs.sort_by_key(|run| run.sequence);
+            for run in runs {
+                entries.push(index_entry(
+                    epoch,
+                    Some(run.sequence),
+                    &run.test_id,
+                    Some(&run.database),
+                    Some(run.depth),
+                    &run.measurement,
+                ));
+            }
+        }
+        entries
+    }
+
+    pub fn client_server_split(&self, run: &TestRun) -> Option<ClientServerSplit> {
+        let attribution = run.measurement.attribution?;
+        let summary = self.summarize(run);
+        let (energy_basis, joules) = match summary.net_joules {
+            Some(net) => ("net", net),
+            None => ("gross", summary.gross_joules?),
+        };
+
+        let cpu = attribution.cpu;
+        let total_cpu = cpu.total_secs();
+        let share_of = |secs: f64| {
+            if total_cpu > 0.0 {
+                secs / total_cpu
+            } else {
+                0.0
+            }
+        };
+        let client_joules = joules * share_of(cpu.client_secs);
+        let server_joules = joules * share_of(cpu.server_secs);
+        let measured = client_joules + server_joules;
+
+        Some(ClientServerSplit {
+            test_id: run.test_id.clone(),
+            database: run.database.clone(),
+            depth: run.depth,
+            epoch: run.epoch,
+            energy_basis,
+            client_cpu_secs: cpu.client_secs,
+            server_cpu_secs: cpu.server_secs,
+            client_joules,
+            server_joules,
+            client_share: if measured > 0.0 {
+                client_joules / measured
+            } else {
+                0.0
+            },
+        })
+    }
+
+    pub fn client_server_splits(&self) -> Vec<ClientServerSplit> {
+        self.runs
+            .iter()
+            .filter_map(|run| self.client_server_split(run))
+            .collect()
+    }
+
+    /// Merges each test's latency histograms across epochs and reports reads and writes separately.
+    pub fn latency_reports(&self) -> Vec<LatencyReport> {
+        let mut groups: Vec<(&TestRun, usize, OperationLatencies)> = Vec::new();
+        for run in &self.runs {
+            let Some(latency) = &run.measurement.latency else {
+                continue;
+            };
+            let existing = groups.iter_mut().find(|(first, _, _)| {
+                first.test_id == run.test_id
+                    && first.database == run.database
+                    && first.depth == run.depth
+            });
+            match existing {
+                Some((_, epochs, merged)) => {
+                    *epochs += 1;
+                    merged.merge(latency);
+                }
+                None => groups.push((run, 1, latency.clone())),
+            }
+        }
+
+        let mut reports = Vec::new();
+        for (run, epochs, latency) in groups {
+            for (operation, histogram) in [("read", &latency.reads), ("write", &latency.writes)] {
+                if let Some(summary) = LatencySummary::of(histogram) {
+                    reports.push(LatencyReport {
+                        test_id: run.test_id.clone(),
+                        database: run.database.clone(),
+                        depth: run.depth,
+                        operation,
+                        epochs,
+                        count: summary.count,
+                        p50_us: summary.p50_us,
+                        p90_us: summary.p90_us,
+                        p99_us: summary.p99_us,
+                        p999_us: summary.p999_us,
+                        max_us: summary.max_us,
+                    });
+                }
+            }
+        }
+        reports
+    }
+
+    pub fn write_summary(&self, dir: &Path, file_name: &str) -> Result<(), Box<dyn Error>> {
+        write_csv(dir, file_name, self.summaries())
+    }
+
+    pub fn write_client_server_report(
+        &self,
+        dir: &Path,
+        file_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        write_csv(dir, file_name, self.client_server_splits())
+    }
+
+    pub fn write_latency_report(&self, dir: &Path, file_name: &str) -> Result<(), Box<dyn Error>> {
+        write_csv(dir, file_name, self.latency_reports())
+    }
+
+    pub fn write_failure_report(&self, dir: &Path, file_name: &str) -> Result<(), Box<dyn Error>> {
+        write_csv(dir, file_name, self.failures.clone())
+    }
+
+    pub fn write_index(&self, dir: &Path, file_name: &str) -> Result<(), Box<dyn Error>> {
+        write_csv(dir, file_name, self.index())
+    }
+}
+
+fn index_entry(
+    epoch: u8,
+    sequence: Option<usize>,
+    test_id: &str,
+    database: Option<&str>,
+    depth: Option<u8>,
+    measurement: &Measurement,
+) -> IndexEntry {
+    IndexEntry {
+        epoch,
+        sequence,
+        test_id: test_id.to_string(),
+        database: database.map(str::to_string),
+        depth,
+        monitor: measurement.monitor.clone(),
+        file: measurement
+            .output_file
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned()),
+        window_start_ms: unix_millis(measurement.started_at),
+        window_end_ms: unix_millis(measurement.ended_at),
+        duration_ms: measurement.duration.as_millis(),
+        package_joules: measurement.package_joules,
+        aborted: measurement.aborted,
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0)
+}
+
+fn write_csv<T: Serialize>(
+    dir: &Path,
+    file_name: &str,
+    rows: Vec<T>,
+) -> Result<(), Box<dyn Error>> {
+    if !dir.exists() {
+        create_all(dir, false)?;
+    }
+
+    let mut writer = csv::Writer::from_path(dir.join(file_name))?;
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn test_case() -> TestCase {
+        serde_json::from_value(json!({
+            "id": "1A", "name": "read", "read_ratio": 1, "write_ratio": 0, "operations": 100
+        }))
+        .unwrap()
+    }
+
+    fn measurement(joules: Option<f64>, secs: u64) -> Measurement {
+        let mut measurement = Measurement::new(
+            "window".to_string(),
+            "simulated-fixed",
+            SystemTime::now(),
+            Duration::from_secs(secs),
+        );
+        measurement.package_joules = joules;
+        measurement
+    }
+
+    fn summarize(idle: Option<(u8, f64, u64)>, gross: Option<f64>, secs: u64) -> RunSummary {
+        let mut results = Results::new();
+        if let Some((epoch, joules, secs)) = idle {
+            results.record_idle(epoch, measurement(Some(joules), secs));
+        }
+        results.record_run(&test_case(), "MongoDB", 3, 1, measurement(gross, secs));
+        results.summaries().remove(0)
+    }
+
+    #[test]
+    fn net_energy_subtracts_idle_power_over_the_run_duration() {
+        // 10 W idle over a 4 s run leaves 60 of the 100 J.
+        let summary = summarize(Some((1, 50.0, 5)), Some(100.0), 4);
+        assert_eq!(summary.idle_watts, Some(10.0));
+        assert_eq!(summary.net_joules, Some(60.0));
+        assert_eq!(summary.net_joules_per_op, Some(0.6));
+        assert_eq!(summary.ops_per_net_joule, Some(100.0 / 60.0));
+    }
+
+    #[test]
+    fn net_energy_uses_the_idle_control_of_the_same_epoch() {
+        let summary = summarize(Some((2, 50.0, 5)), Some(100.0), 4);
+        assert_eq!(summary.idle_watts, None);
+        assert_eq!(summary.net_joules, None);
+        assert_eq!(summary.gross_joules, Some(100.0));
+    }
+
+    #[test]
+    fn net_energy_is_unknown_without_an_idle_measurement() {
+        let summary = summarize(None, Some(100.0), 4);
+        assert_eq!(summary.net_joules, None);
+        assert_eq!(summary.net_joules_per_op, None);
+        assert_eq!(summary.ops_per_net_joule, None);
+    }
+
+    #[test]
+    fn net_energy_is_unknown_without_a_gross_figure() {
+        let summary = summarize(Some((1, 50.0, 5)), None, 4);
+        assert_eq!(summary.idle_watts, Some(10.0));
+        assert_eq!(summary.gross_joules, None);
+        assert_eq!(summary.net_joules, None);
+    }
+
+    #[test]
+    fn a_run_below_idle_power_has_no_operations_per_net_joule() {
+        let summary = summarize(Some((1, 50.0, 5)), Some(30.0), 4);
+        assert_eq!(summary.net_joules, Some(-10.0));
+        assert_eq!(summary.net_joules_per_op, Some(-0.1));
+        assert_eq!(summary.ops_per_net_joule, None);
+    }
+}
@@ -0,0 +1,472 @@
+use crate::benchmark::checkpoint::Checkpoint;
+use crate::benchmark::harness::{
+    clean_and_insert_data, cool_down, exclusive, measure_idle_energy_consumption, run_test_case,
+    Interrupt,
+};
+use crate::benchmark::plan::{BenchmarkPlan, TestCase, TestOrder};
+use crate::benchmark::results::{Failure, Results};
+use crate::benchmark::workload::Bound;
+use crate::databases::database::Database;
+use crate::metrics::{Measurement, PowerMonitor};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::error::Error;
+use std::sync::Arc;
+
+/// One measured test: indexes into the plan's databases, datasets and test cases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub database: usize,
+    pub dataset: usize,
+    pub test: usize,
+}
+
+/// What a database currently holds, so a cell only reloads when its data would differ
+/// from a fresh load.
+#[derive(Debug, Clone, Copy, Default)]
+struct DataState {
+    loaded_depth: Option<u8>,
+    indexed: bool,
+}
+
+impl DataState {
+    fn needs_reload(&self, depth: u8, test: &TestCase) -> bool {
+        self.loaded_depth != Some(depth) || test.reload_data || (self.indexed && !test.use_index)
+    }
+
+    fn loaded(&mut self, depth: u8) {
+        self.loaded_depth = Some(depth);
+        self.indexed = false;
+    }
+
+    fn ran(&mut self, test: &TestCase) {
+        self.indexed |= test.use_index;
+    }
+}
+
+/// The seed a shuffled plan is ordered with: the plan's own, or a fresh one to record.
+pub fn order_seed(plan: &BenchmarkPlan) -> Option<u64> {
+    (plan.order == TestOrder::Shuffled).then(|| plan.seed.unwrap_or_else(rand::random))
+}
+
+/// The cells of every epoch, in the order the plan asks for.
+///
+/// `shuffled` permutes each epoch with one generator seeded by `seed`, so the same seed
+/// reproduces the whole suite. `counterbalanced` reverses the plan order on every other
+/// epoch, giving each cell the same mean position over a pair of epochs.
+pub fn cell_order(plan: &BenchmarkPlan, seed: Option<u64>) -> Vec<Vec<Cell>> {
+    let mut sequential = Vec::new();
+    for database in 0..plan.databases.len() {
+        for dataset in 0..plan.datasets.len() {
+            for test in 0..plan.test_cases.len() {
+                sequential.push(Cell {
+                    database,
+                    dataset,
+                    test,
+                });
+            }
+        }
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed.unwrap_or_default());
+    (0..plan.epochs.len())
+        .map(|epoch_index| {
+            let mut cells = sequential.clone();
+            match plan.order {
+                TestOrder::Sequential => {}
+                TestOrder::Shuffled => cells.shuffle(&mut rng),
+                TestOrder::Counterbalanced => {
+                    if epoch_index % 2 == 1 {
+                        cells.reverse();
+                    }
+                }
+            }
+            cells
+        })
+        .collect()
+}
+
+/// Executes every epoch of `plan`: the C1 idle control first, then every database,
+/// depth and test case in the order given by [`cell_order`].
+///
+/// Each measurement is saved to `checkpoint` as soon as it is taken. Cells and idle controls
+/// the checkpoint already holds are skipped, and their measurements are part of the results.
+/// A cell that fails is retried up to `plan.retries` times and then recorded as a failure,
+/// and the suite carries on with the next cell.
+///
+/// After an interrupt the window in progress is kept in the results as aborted but not
+/// checkpointed, every database is cleaned and disconnected, and the partial results are
+/// returned.
+pub async fn run_plan(
+    plan: &BenchmarkPlan,
+    monitor: &mut dyn PowerMonitor,
+    checkpoint: &mut Checkpoint,
+    interrupt: &Interrupt,
+) -> Result<Results, Box<dyn Error>> {
+    let mut databases: Vec<Arc<dyn Database>> = plan
+        .create_databases()?
+        .into_iter()
+        .map(Arc::from)
+        .collect();
+    println!("Databases initialized");
+
+    let seed = checkpoint.order_seed();
+    if let Some(seed) = seed {
+        println!("Test order shuffled with seed {}", seed);
+    }
+    let mut results = Results::new()
+        .with_order_seed(seed)
+        .with_drift_thresholds(plan.drift);
+    checkpoint.restore(plan, &mut results);
+    if checkpoint.completed_cells() > 0 {
+        println!(
+            "Resuming after {} completed test runs",
+            checkpoint.completed_cells()
+        );
+    }
+
+    'epochs: for (&epoch, cells) in plan.epochs.iter().zip(cell_order(plan, seed)) {
+        let pending: Vec<Cell> = cells
+            .into_iter()
+            .filter(|cell| {
+                !checkpoint.is_done(
+                    epoch,
+                    &plan.databases[cell.database].name,
+                    plan.datasets[cell.dataset].depth,
+                    &plan.test_cases[cell.test].id,
+                )
+            })
+            .collect();
+        if pending.is_empty() {
+            continue;
+        }
+
+        if !checkpoint.has_idle(epoch) {
+            println!("Control Test C1");
+            let idle =
+                measure_idle_energy_consumption(monitor, epoch, plan.idle_duration(), interrupt)
+                    .await?;
+            if idle.aborted {
+                results.record_idle(epoch, idle);
+                break;
+            }
+            checkpoint.record_idle(epoch, &idle)?;
+            results.record_idle(epoch, idle);
+        }
+
+        let context = EpochContext {
+            epoch,
+            idle_watts: results.idle_watts(epoch),
+        };
+        let mut states = vec![DataState::default(); databases.len()];
+        for cell in pending {
+            if interrupt.is_set() {
+                break 'epochs;
+            }
+            let db = &mut databases[cell.database];
+            let state = &mut states[cell.database];
+            let dataset = &plan.datasets[cell.dataset];
+            let test = &plan.test_cases[cell.test];
+
+            let mut attempts = 0;
+            let measurement = loop {
+                attempts += 1;
+                let result = run_cell(db, state, monitor, plan, cell, context, interrupt).await;
+                let error = match result {
+                    Ok(measurement) => break Ok(measurement),
+                    Err(_) if interrupt.is_set() => break 'epochs,
+                    Err(e) => e.to_string(),
+                };
+                eprintln!(
+                    "Test {} - {} Depth {}: Epoch: {} attempt {} failed: {}",
+                    test.id,
+                    db.name(),
+                    dataset.depth,
+                    epoch,
+                    attempts,
+                    error
+                );
+                // Start the next attempt, or the next cell, from a fresh connection and data.
+                *state = DataState::default();
+                if let Err(e) = exclusive(db)?.disconnect().await {
+                    eprintln!("Failed to disconnect from {}: {}", db.name(), e);
+                }
+                if attempts > plan.retries {
+                    break Err(error);
+                }
+            };
+            let measurement = match measurement {
+                Ok(measurement) => measurement,
+                Err(error) => {
+                    results.record_failure(Failure {
+                        test_id: test.id.clone(),
+                        database: db.name().to_string(),
+                        depth: dataset.depth,
+                        epoch,
+                        attempts,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            println!(
+                "Test {} - {} Depth {}: Epoch: {} {}ms{}",
+                test.id,
+                db.name(),
+                dataset.depth,
+                epoch,
+                measurement.duration.as_millis(),
+                if measurement.aborted {
+                    " (aborted)"
+                } else {
+                    ""
+                }
+            );
+            if !measurement.aborted {
+                checkpoint.record_cell(epoch, db.name(), dataset.depth, &test.id, &measurement)?;
+            }
+            results.record_run(test, db.name(), dataset.depth, epoch, measurement);
+        }
+
+        for db in databases.iter_mut() {
+            exclusive(db)?.disconnect().await?;
+        }
+    }
+
+    if interrupt.is_set() {
+        // Don't leave half-loaded tables and collections or open clients behind.
+        for db in databases.iter_mut() {
+            if let Err(e) = db.clean_database().await {
+                eprintln!("Failed to clean {}: {}", db.name(), e);
+            }
+            if let Err(e) = exclusive(db)?.disconnect().await {
+                eprintln!("Failed to disconnect from {}: {}", db.name(), e);
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// What every cell of an epoch shares.
+#[derive(Debug, Clone, Copy)]
+struct EpochContext {
+    epoch: u8,
+    idle_watts: Option<f64>,
+}
+
+/// Loads the cell's dataset if the database doesn't hold it yet, cools down and measures it.
+/// An interrupt abandons the data load.
+async fn run_cell(
+    db: &mut Arc<dyn Database>,
+    state: &mut DataState,
+    monitor: &mut dyn PowerMonitor,
+    plan: &BenchmarkPlan,
+    cell: Cell,
+    context: EpochContext,
+    interrupt: &Interrupt,
+) -> Result<Measurement, Box<dyn Error>> {
+    let dataset = &plan.datasets[cell.dataset];
+    let test = &plan.test_cases[cell.test];
+    if state.needs_reload(dataset.depth, test) {
+        let target = exclusive(db)?;
+        tokio::select! {
+            result = clean_and_insert_data(target, dataset, plan.insert_batch_size) => result?,
+            _ = interrupt.wait() => return Err("Interrupted while loading data".into()),
+        }
+        state.loaded(dataset.depth);
+    }
+    cool_down(
+        monitor,
+        &plan.cooldown,
+        context.idle_watts,
+        context.epoch,
+        interrupt,
+    )
+    .await?;
+    if interrupt.is_set() {
+        return Err("Interrupted before the measurement window opened".into());
+    }
+    let measurement = run_test_case(
+        db,
+        monitor,
+        test,
+        dataset.depth,
+        plan.num_docs,
+        context.epoch,
+        interrupt,
+    )
+    .await?;
+    state.ran(test);
+    Ok(measurement)
+}
+
+/// The steps `run_plan` would take, one line each, without touching databases or monitors.
+pub fn describe_plan(plan: &BenchmarkPlan) -> Vec<String> {
+    let mut steps = Vec::new();
+    let seed = order_seed(plan);
+    match (seed, plan.seed) {
+        (Some(seed), Some(_)) => steps.push(format!("Test order shuffled with seed {}", seed)),
+        (Some(seed), None) => steps.push(format!(
+            "Test order shuffled with seed {}, drawn for this dry run only; a real run draws \
+             its own seed, so set \"seed\": {} in the plan to run this order",
+            seed, seed
+        )),
+        (None, _) => {}
+    }
+
+    for (&epoch, cells) in plan.epochs.iter().zip(cell_order(plan, seed)) {
+        steps.push(format!(
+            "Epoch {}: Control Test C1 idle for {}s with the {} monitor",
+            epoch, plan.idle_duration_secs, plan.monitor
+        ));
+
+        let mut states = vec![DataState::default(); plan.databases.len()];
+        for cell in cells {
+            let db = &plan.databases[cell.database];
+            let state = &mut states[cell.database];
+            let dataset = &plan.datasets[cell.dataset];
+            let test = &plan.test_cases[cell.test];
+
+            if state.needs_reload(dataset.depth, test) {
+                steps.push(format!(
+                    "Epoch {}: load {} into {} in batches of {}",
+                    epoch,
+                    dataset.path().display(),
+                    db.name,
+                    plan.insert_batch_size
+                ));
+                state.loaded(dataset.depth);
+            }
+            if let Some(step) = describe_cooldown(plan) {
+                steps.push(format!("Epoch {}: {}", epoch, step));
+            }
+            state.ran(test);
+            steps.push(format!(
+                "Epoch {}: Test {} - {} Depth {}: {} on {} client(s){}",
+                epoch,
+                test.id,
+                db.name,
+                dataset.depth,
+                describe_workload(test),
+                test.clients,
+                if test.use_index { " with index" } else { "" }
+            ));
+        }
+    }
+    steps
+}
+
+fn describe_cooldown(plan: &BenchmarkPlan) -> Option<String> {
+    let cooldown = &plan.cooldown;
+    match (cooldown.secs, cooldown.stabilize_within_pct) {
+        (0, None) => None,
+        (secs, None) => Some(format!("cool down for {}s", secs)),
+        (secs, Some(pct)) => Some(format!(
+            "cool down for {}s, then wait up to {}s for package power within {}% of idle",
+            secs, cooldown.stabilize_timeout_secs, pct
+        )),
+    }
+}
+
+/// The operations a test case runs and when it stops, e.g. `100 reads, 20 writes` or
+/// `5:1 reads/writes for 120s`.
+pub fn describe_workload(test: &TestCase) -> String {
+    let mix = test.op_mix();
+    match (test.bound(), test.open_loop()) {
+        (_, Some(pacing)) => format!(
+            "{} reads, {} writes at {} ops/s for {}s",
+            mix.reads,
+            mix.writes,
+            pacing.rate,
+            pacing.duration.as_secs()
+        ),
+        (Bound::Operations, None) => format!("{} reads, {} writes", mix.reads, mix.writes),
+        (Bound::Duration(duration), _) => format!(
+            "{}:{} reads/writes for {}s",
+            mix.reads,
+            mix.writes,
+            duration.as_secs()
+        ),
+        (Bound::EnergyBudget(joules), _) => format!(
+            "{}:{} reads/writes until {} J",
+            mix.reads, mix.writes, joules
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn plan(order: &str) -> BenchmarkPlan {
+        serde_json::from_value(json!({
+            "epochs": [1, 2, 3, 4],
+            "idle_duration_secs": 1,
+            "num_docs": 10,
+            "order": order,
+            "databases": [{ "name": "MongoDB" }, { "name": "PostgreSQL" }],
+            "datasets": [{ "depth": 3 }, { "depth": 5 }],
+            "test_cases": [
+                { "id": "1A", "name": "a", "read_ratio": 1, "write_ratio": 0, "operations": 10 },
+                { "id": "1B", "name": "b", "read_ratio": 0, "write_ratio": 1, "operations": 10 },
+                { "id": "2A", "name": "c", "read_ratio": 1, "write_ratio": 1, "operations": 10 }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn sorted(cells: &[Cell]) -> Vec<(usize, usize, usize)> {
+        let mut cells: Vec<_> = cells
+            .iter()
+            .map(|cell| (cell.database, cell.dataset, cell.test))
+            .collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn sequential_order_runs_databases_then_depths_then_tests() {
+        let order = cell_order(&plan("sequential"), None);
+        assert_eq!(order.len(), 4);
+        let expected = sorted(&order[0]);
+        for cells in &order {
+            let cells: Vec<_> = cells
+                .iter()
+                .map(|cell| (cell.database, cell.dataset, cell.test))
+                .collect();
+            assert_eq!(cells, expected);
+        }
+        assert_eq!(expected.len(), 12);
+    }
+
+    #[test]
+    fn shuffled_order_is_reproducible_from_its_seed() {
+        let shuffled = plan("shuffled");
+        let order = cell_order(&shuffled, Some(42));
+
+        assert_eq!(cell_order(&shuffled, Some(42)), order);
+        assert_ne!(cell_order(&shuffled, Some(43)), order);
+        // Every epoch runs every cell exactly once, in an order of its own.
+        let all = sorted(&cell_order(&plan("sequential"), None)[0]);
+        for cells in &order {
+            assert_eq!(sorted(cells), all);
+        }
+        assert_ne!(order[0], order[1]);
+    }
+
+    #[test]
+    fn counterbalanced_order_reverses_every_other_epoch() {
+        let sequential = cell_order(&plan("sequential"), None).remove(0);
+        let mut reversed = sequential.clone();
+        reversed.reverse();
+
+        let order = cell_order(&plan("counterbalanced"), None);
+        assert_eq!(
+            order,
+            vec![sequential.clone(), reversed.clone(), sequential, reversed]
+        );
+    }
+}
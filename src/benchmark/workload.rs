@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+/// A single workload operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Read,
+    Write,
+}
+
+/// How many reads and writes a workload performs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpMix {
+    pub reads: usize,
+    pub writes: usize,
+}
+
+impl OpMix {
+    pub fn new(reads: usize, writes: usize) -> Self {
+        OpMix { reads, writes }
+    }
+
+    /// Splits `operations` by a read:write ratio, rounding the read count to the nearest operation.
+    /// A ratio side of 0 gives a pure-read or pure-write workload.
+    pub fn from_ratio(read_ratio: u32, write_ratio: u32, operations: usize) -> Self {
+        let total_ratio = read_ratio as u128 + write_ratio as u128;
+        if total_ratio == 0 {
+            return OpMix::new(0, 0);
+        }
+        let reads = (operations as u128 * read_ratio as u128 + total_ratio / 2) / total_ratio;
+        let reads = reads as usize;
+        OpMix::new(reads, operations - reads)
+    }
+
+    /// The smallest mix with the given ratio, repeated by workloads without a fixed operation count.
+    pub fn period(read_ratio: u32, write_ratio: u32) -> Self {
+        let divisor = gcd(read_ratio, write_ratio).max(1);
+        OpMix::new(
+            (read_ratio / divisor) as usize,
+            (write_ratio / divisor) as usize,
+        )
+    }
+
+    pub fn total(&self) -> usize {
+        self.reads + self.writes
+    }
+
+    /// The order in which every backend executes the operations.
+    ///
+    /// Reads are spread as evenly as possible among the writes: after any prefix of `n`
+    /// operations exactly `floor(n * reads / total)` of them are reads, so 3:2 runs
+    /// W R W R R and 5:1 runs W R R R R R.
+    pub fn schedule(&self) -> Schedule {
+        Schedule {
+            mix: *self,
+            position: 0,
+            reads_done: 0,
+        }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Iterator over the interleaved operations of an [`OpMix`].
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    mix: OpMix,
+    position: usize,
+    reads_done: usize,
+}
+
+impl Iterator for Schedule {
+    type Item = Operation;
+
+    fn next(&mut self) -> Option<Operation> {
+        let total = self.mix.total();
+        if self.position == total {
+            return None;
+        }
+        self.position += 1;
+        let reads_due = (self.position as u128 * self.mix.reads as u128 / total as u128) as usize;
+        if reads_due > self.reads_done {
+            self.reads_done += 1;
+            Some(Operation::Read)
+        } else {
+            Some(Operation::Write)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.mix.total() - self.position;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Schedule {}
+
+/// Open-loop pacing: operations are issued at a fixed rate for a fixed time instead of back to back,
+/// so every database is compared at the same delivered throughput.
+#[derive(Debug, Clone, Copy)]
+pub struct OpenLoop {
+    /// Target operations per second, across all clients.
+    pub rate: f64,
+    pub duration: Duration,
+}
+
+impl OpenLoop {
+    pub fn operations(&self) -> usize {
+        (self.rate * self.duration.as_secs_f64()).round() as usize
+    }
+
+    /// When operation `index` of the schedule is due, relative to the start of the workload.
+    pub fn deadline(&self, index: usize) -> Duration {
+        Duration::from_secs_f64(index as f64 / self.rate)
+    }
+}
+
+/// When a workload stops.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    /// After every operation of the mix has run.
+    Operations,
+    /// After this much wall-clock time, repeating the mix's schedule as often as needed.
+    Duration(Duration),
+    /// Once the power monitor has measured this many package joules, repeating the mix's schedule.
+    EnergyBudget(f64),
+}
+
+impl Bound {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Bound::Operations => "operations",
+            Bound::Duration(_) => "duration",
+            Bound::EnergyBudget(_) => "energy_budget",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Operation::{Read, Write};
+
+    /// Checks the documented prefix property and returns the schedule.
+    fn checked_schedule(mix: OpMix) -> Vec<Operation> {
+        let schedule: Vec<Operation> = mix.schedule().collect();
+        assert_eq!(schedule.len(), mix.total());
+        let mut reads = 0;
+        for (i, operation) in schedule.iter().enumerate() {
+            if *operation == Read {
+                reads += 1;
+            }
+            assert_eq!(reads, (i + 1) * mix.reads / mix.total(), "prefix {}", i + 1);
+        }
+        schedule
+    }
+
+    #[test]
+    fn three_to_two() {
+        assert_eq!(OpMix::from_ratio(3, 2, 5), OpMix::new(3, 2));
+        assert_eq!(OpMix::from_ratio(3, 2, 7), OpMix::new(4, 3));
+        assert_eq!(OpMix::period(3, 2), OpMix::new(3, 2));
+        assert_eq!(
+            checked_schedule(OpMix::new(3, 2)),
+            vec![Write, Read, Write, Read, Read]
+        );
+        checked_schedule(OpMix::from_ratio(3, 2, 1000));
+    }
+
+    #[test]
+    fn five_to_one() {
+        assert_eq!(OpMix::from_ratio(5, 1, 1200), OpMix::new(1000, 200));
+        // 5/6 of 10 is 8.33, rounded to 8 reads.
+        assert_eq!(OpMix::from_ratio(5, 1, 10), OpMix::new(8, 2));
+        assert_eq!(OpMix::period(10, 2), OpMix::new(5, 1));
+        assert_eq!(
+            checked_schedule(OpMix::new(5, 1)),
+            vec![Write, Read, Read, Read, Read, Read]
+        );
+        checked_schedule(OpMix::from_ratio(5, 1, 1200));
+    }
+
+    #[test]
+    fn ninety_five_to_five() {
+        assert_eq!(OpMix::from_ratio(95, 5, 1000), OpMix::new(950, 50));
+        // 95% of 30 is 28.5, rounded up to 29 reads.
+        assert_eq!(OpMix::from_ratio(95, 5, 30), OpMix::new(29, 1));
+        assert_eq!(OpMix::period(95, 5), OpMix::new(19, 1));
+        let schedule = checked_schedule(OpMix::from_ratio(95, 5, 1000));
+        assert_eq!(schedule.iter().filter(|&&op| op == Write).count(), 50);
+    }
+
+    #[test]
+    fn pure_reads() {
+        assert_eq!(OpMix::from_ratio(100, 0, 40), OpMix::new(40, 0));
+        assert_eq!(OpMix::period(100, 0), OpMix::new(1, 0));
+        assert_eq!(checked_schedule(OpMix::new(40, 0)), vec![Read; 40]);
+    }
+
+    #[test]
+    fn pure_writes() {
+        assert_eq!(OpMix::from_ratio(0, 100, 40), OpMix::new(0, 40));
+        assert_eq!(OpMix::period(0, 100), OpMix::new(0, 1));
+        assert_eq!(checked_schedule(OpMix::new(0, 40)), vec![Write; 40]);
+    }
+
+    #[test]
+    fn no_ratio_gives_no_operations() {
+        assert_eq!(OpMix::from_ratio(0, 0, 40), OpMix::new(0, 0));
+        assert_eq!(OpMix::new(0, 0).schedule().count(), 0);
+    }
+}
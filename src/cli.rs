@@ -0,0 +1,306 @@
+use crate::benchmark::output::DEFAULT_RESULTS_ROOT;
+use crate::benchmark::plan::PlanFilter;
+use std::error::Error;
+use std::path::PathBuf;
+
+pub const DEFAULT_PLAN: &str = "plans/default.json";
+pub const DEFAULT_DEPTHS: &[u8] = &[3, 5, 10];
+pub const DEFAULT_DOCUMENTS_PER_DEPTH: usize = 10000;
+
+pub const USAGE: &str = "\
+Usage: energy_benchmark <command> [options]
+
+Commands:
+  run       Execute a benchmark plan
+  generate  Generate hierarchical test datasets
+  analyze   Summarize PCM measurement files
+  list      Show registered databases, power monitors and the plan's test cases
+  help      Show this message
+
+run options:
+  --plan <path>       Plan file (default: plans/default.json)
+  --database <name>   Only run this database (repeatable)
+  --test <id>         Only run this test case (repeatable)
+  --depth <n>         Only run this dataset depth (repeatable)
+  --monitor <name>    Override the plan's power monitor
+  --results <dir>     Root of the per-run result directories (default: results)
+  --resume            Continue the most recent interrupted run under the results root
+  --dry-run           Print the steps without touching databases or monitors
+
+generate options:
+  --depth <n>         Depth to generate (repeatable, default: 3, 5 and 10)
+  --count <n>         Documents per depth (default: 10000)
+  --dry-run           Print what would be generated
+
+analyze [<path>...]   PCM CSV files or directories (default: the most recent run)
+  --results <dir>     Root the most recent run is looked up in (default: results)
+
+list options:
+  --plan <path>       Plan whose test cases are listed (default: plans/default.json)";
+
+#[derive(Debug)]
+pub enum Command {
+    Run(RunArgs),
+    Generate(GenerateArgs),
+    Analyze {
+        paths: Vec<PathBuf>,
+        results_root: PathBuf,
+    },
+    List {
+        plan: PathBuf,
+    },
+    Help,
+}
+
+#[derive(Debug)]
+pub struct RunArgs {
+    pub plan: PathBuf,
+    pub filter: PlanFilter,
+    pub monitor: Option<String>,
+    pub results_root: PathBuf,
+    pub resume: bool,
+    pub dry_run: bool,
+}
+
+#[derive(Debug)]
+pub struct GenerateArgs {
+    pub depths: Vec<u8>,
+    pub documents: usize,
+    pub dry_run: bool,
+}
+
+/// Parses the arguments following the program name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, Box<dyn Error>> {
+    let mut args = args.into_iter();
+    let Some(command) = args.next() else {
+        return Ok(Command::Help);
+    };
+    let mut options = Options { args };
+
+    match command.as_str() {
+        "run" => {
+            let mut run = RunArgs {
+                plan: PathBuf::from(DEFAULT_PLAN),
+                filter: PlanFilter::default(),
+                monitor: None,
+                results_root: PathBuf::from(DEFAULT_RESULTS_ROOT),
+                resume: false,
+                dry_run: false,
+            };
+            while let Some(flag) = options.args.next() {
+                match flag.as_str() {
+                    "--plan" => run.plan = PathBuf::from(options.value(&flag)?),
+                    "--database" => run.filter.databases.push(options.value(&flag)?),
+                    "--test" => run.filter.test_ids.push(options.value(&flag)?),
+                    "--depth" => run.filter.depths.push(options.parsed(&flag)?),
+                    "--monitor" => run.monitor = Some(options.value(&flag)?),
+                    "--results" => run.results_root = PathBuf::from(options.value(&flag)?),
+                    "--resume" => run.resume = true,
+                    "--dry-run" => run.dry_run = true,
+                    _ => return Err(unexpected(&command, &flag)),
+                }
+            }
+            Ok(Command::Run(run))
+        }
+        "generate" => {
+            let mut generate = GenerateArgs {
+                depths: Vec::new(),
+                documents: DEFAULT_DOCUMENTS_PER_DEPTH,
+                dry_run: false,
+            };
+            while let Some(flag) = options.args.next() {
+                match flag.as_str() {
+                    "--depth" => generate.depths.push(options.parsed(&flag)?),
+                    "--count" => generate.documents = options.parsed(&flag)?,
+                    "--dry-run" => generate.dry_run = true,
+                    _ => return Err(unexpected(&command, &flag)),
+                }
+            }
+            if generate.depths.is_empty() {
+                generate.depths = DEFAULT_DEPTHS.to_vec();
+            }
+            if generate.depths.contains(&0) {
+                return Err("generate: --depth must be at least 1".into());
+            }
+            if generate.documents == 0 {
+                return Err("generate: --count must be greater than 0".into());
+            }
+            Ok(Command::Generate(generate))
+        }
+        "analyze" => {
+            let mut paths = Vec::new();
+            let mut results_root = PathBuf::from(DEFAULT_RESULTS_ROOT);
+            while let Some(arg) = options.args.next() {
+                match arg.as_str() {
+                    "--results" => results_root = PathBuf::from(options.value(&arg)?),
+                    _ if arg.starts_with("--") => return Err(unexpected(&command, &arg)),
+                    _ => paths.push(PathBuf::from(arg)),
+                }
+            }
+            Ok(Command::Analyze {
+                paths,
+                results_root,
+            })
+        }
+        "list" => {
+            let mut plan = PathBuf::from(DEFAULT_PLAN);
+            while let Some(flag) = options.args.next() {
+                match flag.as_str() {
+                    "--plan" => plan = PathBuf::from(options.value(&flag)?),
+                    _ => return Err(unexpected(&command, &flag)),
+                }
+            }
+            Ok(Command::List { plan })
+        }
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        _ => Err(format!("Unknown command '{}'\n\n{}", command, USAGE).into()),
+    }
+}
+
+struct Options<I> {
+    args: I,
+}
+
+impl<I: Iterator<Item = String>> Options<I> {
+    fn value(&mut self, flag: &str) -> Result<String, Box<dyn Error>> {
+        self.args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag).into())
+    }
+
+    fn parsed<T: std::str::FromStr>(&mut self, flag: &str) -> Result<T, Box<dyn Error>> {
+        let value = self.value(flag)?;
+        value
+            .parse()
+            .map_err(|_| format!("Invalid value '{}' for {}", value, flag).into())
+    }
+}
+
+fn unexpected(command: &str, arg: &str) -> Box<dyn Error> {
+    format!("Unexpected argument '{}' for {}\n\n{}", arg, command, USAGE).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, Box<dyn Error>> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn parse_err(args: &[&str]) -> String {
+        parse(args).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn no_command_shows_help() {
+        assert!(matches!(parse(&[]).unwrap(), Command::Help));
+        assert!(matches!(parse(&["--help"]).unwrap(), Command::Help));
+    }
+
+    #[test]
+    fn run_defaults() {
+        let Command::Run(run) = parse(&["run"]).unwrap() else {
+            panic!("expected run");
+        };
+        assert_eq!(run.plan, PathBuf::from(DEFAULT_PLAN));
+        assert_eq!(run.results_root, PathBuf::from(DEFAULT_RESULTS_ROOT));
+        assert!(run.filter.databases.is_empty());
+        assert!(run.monitor.is_none());
+        assert!(!run.resume);
+        assert!(!run.dry_run);
+    }
+
+    #[test]
+    fn run_collects_repeated_filters_and_flags() {
+        let Command::Run(run) = parse(&[
+            "run",
+            "--plan",
+            "plans/small.json",
+            "--database",
+            "MongoDB",
+            "--database",
+            "PostgreSQL",
+            "--test",
+            "1A",
+            "--depth",
+            "3",
+            "--depth",
+            "5",
+            "--monitor",
+            "rapl",
+            "--results",
+            "out",
+            "--resume",
+            "--dry-run",
+        ])
+        .unwrap() else {
+            panic!("expected run");
+        };
+        assert_eq!(run.plan, PathBuf::from("plans/small.json"));
+        assert_eq!(run.filter.databases, vec!["MongoDB", "PostgreSQL"]);
+        assert_eq!(run.filter.test_ids, vec!["1A"]);
+        assert_eq!(run.filter.depths, vec![3, 5]);
+        assert_eq!(run.monitor.as_deref(), Some("rapl"));
+        assert_eq!(run.results_root, PathBuf::from("out"));
+        assert!(run.resume);
+        assert!(run.dry_run);
+    }
+
+    #[test]
+    fn flags_need_valid_values() {
+        assert!(parse_err(&["run", "--plan"]).contains("--plan needs a value"));
+        assert!(parse_err(&["run", "--depth", "deep"]).contains("Invalid value 'deep' for --depth"));
+        assert!(
+            parse_err(&["run", "--verbose"]).contains("Unexpected argument '--verbose' for run")
+        );
+        assert!(parse_err(&["bench"]).contains("Unknown command 'bench'"));
+    }
+
+    #[test]
+    fn generate_defaults_to_every_depth_and_rejects_empty_output() {
+        let Command::Generate(generate) = parse(&["generate"]).unwrap() else {
+            panic!("expected generate");
+        };
+        assert_eq!(generate.depths, DEFAULT_DEPTHS);
+        assert_eq!(generate.documents, DEFAULT_DOCUMENTS_PER_DEPTH);
+
+        let Command::Generate(generate) =
+            parse(&["generate", "--depth", "4", "--count", "10", "--dry-run"]).unwrap()
+        else {
+            panic!("expected generate");
+        };
+        assert_eq!(generate.depths, vec![4]);
+        assert_eq!(generate.documents, 10);
+        assert!(generate.dry_run);
+
+        assert!(parse_err(&["generate", "--depth", "0"]).contains("at least 1"));
+        assert!(parse_err(&["generate", "--count", "0"]).contains("greater than 0"));
+    }
+
+    #[test]
+    fn analyze_takes_paths_and_a_results_root() {
+        let Command::Analyze {
+            paths,
+            results_root,
+        } = parse(&["analyze", "a.csv", "--results", "out", "runs/run_1"]).unwrap()
+        else {
+            panic!("expected analyze");
+        };
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("a.csv"), PathBuf::from("runs/run_1")]
+        );
+        assert_eq!(results_root, PathBuf::from("out"));
+
+        assert!(parse_err(&["analyze", "--all"]).contains("Unexpected argument '--all'"));
+    }
+
+    #[test]
+    fn list_takes_a_plan() {
+        let Command::List { plan } = parse(&["list", "--plan", "p.json"]).unwrap() else {
+            panic!("expected list");
+        };
+        assert_eq!(plan, PathBuf::from("p.json"));
+    }
+}
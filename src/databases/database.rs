@@ -1,25 +1,57 @@
 use async_trait::async_trait;
 use serde_json::Value;
 use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use super::mongodb::MongoDB;
+use super::postgres::PostgreSQL;
+
+/// Names accepted by [`create_database`].
+pub const DATABASE_NAMES: &[&str] = &["MongoDB", "PostgreSQL"];
 
 #[async_trait]
-pub trait Database {
+pub trait Database: Send + Sync {
     fn name(&self) -> &'static str;
+    /// Process names of the database server, used to attribute energy to it.
+    fn server_processes(&self) -> &'static [&'static str];
+    /// cgroup v2 the server runs in, if its resource usage should be recorded.
+    fn cgroup(&self) -> Option<&Path>;
     async fn connect(&mut self) -> Result<(), Box<dyn Error>>;
     async fn disconnect(&mut self) -> Result<(), Box<dyn Error>>;
+    /// Version string the connected server reports about itself.
+    async fn server_version(&self) -> Result<String, Box<dyn Error>>;
     async fn clean_database(&self) -> Result<(), Box<dyn Error>>;
     async fn insert_test_data(
         &self,
         batch_size: usize,
         test_data: Vec<Value>,
     ) -> Result<(), Box<dyn Error>>;
-    async fn run_queries(
-        &self,
+    async fn prepare_indexes(&self, depth: u8) -> Result<(), Box<dyn Error>>;
+    /// Builds the read and write statements for `depth` and opens `clients` independent
+    /// connections before the measurement window opens.
+    async fn prepare_workload(
+        &mut self,
         depth: u8,
-        num_reads: usize,
-        num_writes: usize,
-        use_index: bool,
         num_docs: usize,
-        measurement_name: &str,
+        clients: usize,
     ) -> Result<(), Box<dyn Error>>;
+    /// Runs one read prepared by [`Database::prepare_workload`] on connection `client`.
+    async fn execute_read(&self, client: usize) -> Result<(), Box<dyn Error>>;
+    /// Runs one write prepared by [`Database::prepare_workload`] on connection `client`.
+    async fn execute_write(&self, client: usize) -> Result<(), Box<dyn Error>>;
+}
+
+/// Builds the database backend registered under `name`.
+pub fn create_database(
+    name: &str,
+    cgroup: Option<PathBuf>,
+) -> Result<Box<dyn Database>, Box<dyn Error>> {
+    let db: Box<dyn Database> = match (name, cgroup) {
+        ("MongoDB", Some(path)) => Box::new(MongoDB::new().with_cgroup(path)),
+        ("MongoDB", None) => Box::new(MongoDB::new()),
+        ("PostgreSQL", Some(path)) => Box::new(PostgreSQL::new().with_cgroup(path)),
+        ("PostgreSQL", None) => Box::new(PostgreSQL::new()),
+        (other, _) => return Err(format!("Unknown database: {}", other).into()),
+    };
+    Ok(db)
 }
@@ -1,5 +1,4 @@
 use super::database::Database;
-use crate::metrics::{start_power_monitoring, stop_power_monitoring};
 use crate::data_generator::generate_fixed_size_word;
 use async_trait::async_trait;
 use bson::{doc, to_document, Document};
@@ -9,9 +8,21 @@ use mongodb::options::{AggregateOptions, ClientOptions};
 
 use serde_json::Value;
 use std::error::Error;
+use std::path::{Path, PathBuf};
 
 pub struct MongoDB {
-    client: Option<Client>
+    client: Option<Client>,
+    cgroup: Option<PathBuf>,
+    workload: Option<MongoWorkload>,
+}
+
+/// Statements and worker clients opened by `prepare_workload`, one client per concurrent worker.
+struct MongoWorkload {
+    read_pipeline: Vec<Document>,
+    read_options: AggregateOptions,
+    update_filter: Document,
+    update: Document,
+    clients: Vec<Client>,
 }
 
 #[async_trait]
@@ -20,20 +31,35 @@ impl Database for MongoDB {
         "MongoDB"
     }
 
+    fn server_processes(&self) -> &'static [&'static str] {
+        &["mongod"]
+    }
+
+    fn cgroup(&self) -> Option<&Path> {
+        self.cgroup.as_deref()
+    }
+
     async fn connect(&mut self) -> Result<(), Box<dyn Error>> {
-        let client_options = ClientOptions::parse("mongodb://localhost:27017").await?;
-        let client: Client = Client::with_options(client_options)?;
-        self.client = Some(client);
+        self.client = Some(open_client().await?);
         Ok(())
     }
 
     async fn disconnect(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(workload) = self.workload.take() {
+            workload.shutdown().await;
+        }
         if let Some(client) = self.client.take() {
             client.shutdown().await;
         }
         Ok(())
     }
 
+    async fn server_version(&self) -> Result<String, Box<dyn Error>> {
+        let client = self.client.as_ref().ok_or("MongoDB client is not connected")?;
+        let info = client.database("admin").run_command(doc! { "buildInfo": 1 }, None).await?;
+        Ok(info.get_str("version")?.to_string())
+    }
+
     async fn clean_database(&self) -> Result<(), Box<dyn Error>> {
         if let Some(client) = &self.client {
             let db = client.database("benchmark");
@@ -73,57 +99,108 @@ impl Database for MongoDB {
         Ok(())
     }
 
-    async fn run_queries(
-        &self,
-        depth: u8,
-        num_reads: usize,
-        num_writes: usize,
-        use_index: bool,
-        num_docs: usize,
-        measurement_name: &str,
-    ) -> Result<(), Box<dyn Error>> {
-        if use_index {
-            self.create_index(depth).await?;
-        }
+    async fn prepare_indexes(&self, depth: u8) -> Result<(), Box<dyn Error>> {
+        let path = self.create_read_path(depth);
+        let index_keys = doc! { &path: 1 };
 
         if let Some(client) = &self.client {
             let db = client.database("benchmark");
             let collection = db.collection::<Document>("data");
 
-            let mut pcm_process = start_power_monitoring(measurement_name);
-            if num_reads > num_writes {
-                self.run_read_heavy(num_reads, num_writes, depth, num_docs, collection)
-                    .await?;
-            } else {
-                self.run_write_heavy(num_reads, num_writes, depth, num_docs, collection)
-                    .await?;
-            }
-            stop_power_monitoring(&mut pcm_process);
+            let index_model = IndexModel::builder().keys(index_keys).build();
+            collection.create_index(index_model, None).await?;
+        }
+        Ok(())
+    }
+
+    async fn prepare_workload(
+        &mut self,
+        depth: u8,
+        num_docs: usize,
+        clients: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(previous) = self.workload.take() {
+            previous.shutdown().await;
         }
 
+        let mut worker_clients = Vec::with_capacity(clients);
+        for _ in 0..clients {
+            let client = open_client().await?;
+            // The driver connects lazily; do discovery and the handshake before the window opens.
+            client.database("benchmark").run_command(doc! { "ping": 1 }, None).await?;
+            worker_clients.push(client);
+        }
+
+        self.workload = Some(MongoWorkload {
+            // Pipeline so, that it doesn't send an entire document over the network causing noice.
+            read_pipeline: self.create_read_pipeline(depth),
+            read_options: self.create_read_options(num_docs as u32),
+            update_filter: self.create_read_filter(depth),
+            update: self.create_update(depth),
+            clients: worker_clients,
+        });
+        Ok(())
+    }
+
+    async fn execute_read(&self, client: usize) -> Result<(), Box<dyn Error>> {
+        let workload = self.workload.as_ref().ok_or("MongoDB workload was not prepared")?;
+        let collection = workload.collection(client)?;
+        let cursor = collection
+            .aggregate(workload.read_pipeline.clone(), Some(workload.read_options.clone()))
+            .await?;
+        let _docs:Vec<Document> = cursor.try_collect().await?;
+        Ok(())
+    }
+
+    async fn execute_write(&self, client: usize) -> Result<(), Box<dyn Error>> {
+        let workload = self.workload.as_ref().ok_or("MongoDB workload was not prepared")?;
+        let collection = workload.collection(client)?;
+        collection
+            .update_many(workload.update_filter.clone(), workload.update.clone(), None)
+            .await?;
         Ok(())
     }
 }
 
+async fn open_client() -> Result<Client, Box<dyn Error>> {
+    let client_options = ClientOptions::parse("mongodb://localhost:27017").await?;
+    Ok(Client::with_options(client_options)?)
+}
+
+impl MongoWorkload {
+    fn collection(&self, index: usize) -> Result<Collection<Document>, Box<dyn Error>> {
+        let client = self
+            .clients
+            .get(index)
+            .ok_or_else(|| format!("MongoDB client {} was not opened", index))?;
+        Ok(client.database("benchmark").collection::<Document>("data"))
+    }
+
+    async fn shutdown(self) {
+        for client in self.clients {
+            client.shutdown().await;
+        }
+    }
+}
+
+impl Default for MongoDB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MongoDB {
     pub fn new() -> Self {
         MongoDB {
-            client: None
+            client: None,
+            cgroup: None,
+            workload: None,
         }
     }
 
-    async fn create_index(&self, depth: u8) -> Result<(), Box<dyn Error>> {
-        let path = self.create_read_path(depth);
-        let index_keys = doc! { &path: 1 };
-
-        if let Some(client) = &self.client {
-            let db = client.database("benchmark");
-            let collection = db.collection::<Document>("data");
-
-            let index_model = IndexModel::builder().keys(index_keys).build();
-            collection.create_index(index_model, None).await?;
-        }
-        Ok(())
+    pub fn with_cgroup(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cgroup = Some(path.into());
+        self
     }
 
     fn create_read_path(&self, depth: u8) -> String {
@@ -172,61 +249,4 @@ impl MongoDB {
             }        
         ]
     }
-
-    async fn run_read_heavy(
-        &self,
-        num_reads: usize,
-        num_writes: usize,
-        depth: u8,
-        num_doc: usize,
-        collection: Collection<Document>,
-    ) -> Result<(), Box<dyn Error>> {
-        // Pipeline so, that it doesn't send an entire document over the network causing noice.
-        let read_pipeline = self.create_read_pipeline(depth); 
-        let options = self.create_read_options(num_doc as u32);
-        let filter = self.create_read_filter(depth); 
-        let update = self.create_update(depth);
-
-        for _ in 0..num_writes {
-            for _ in 0..(num_reads / num_writes) {
-                let cursor = collection
-                    .aggregate(read_pipeline.clone(), Some(options.clone()))
-                    .await?;
-                let _docs:Vec<Document> = cursor.try_collect().await?;
-            }
-            collection
-                .update_many(filter.clone(), update.clone(), None)
-                .await?;
-        }
-        Ok(())
-    }
-
-    async fn run_write_heavy(
-        &self,
-        num_reads: usize,
-        num_writes: usize,
-        depth: u8,
-        num_doc: usize,
-        collection: Collection<Document>,
-    ) -> Result<(), Box<dyn Error>> {
-        let read_filter = self.create_read_pipeline(depth);
-        let update_filter = self.create_read_filter(depth);
-        let update = self.create_update(depth);
-        let read_options = self.create_read_options(num_doc as u32);
-
-        for _ in 0..num_reads {
-            for _ in 0..(num_writes / num_reads) {
-                collection
-                    .update_many(update_filter.clone(), update.clone(), None)
-                    .await?;
-            }
-
-            let cursor = collection
-                .aggregate(read_filter.clone(), Some(read_options.clone()))
-                .await?;
-            
-            let _docs:Vec<Document> = cursor.try_collect().await?;
-        }
-        Ok(())
-    }
-}
\ No newline at end of file
+}
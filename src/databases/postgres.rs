@@ -1,12 +1,13 @@
 use crate::data_generator::generate_fixed_size_word;
-use crate::metrics::{start_power_monitoring, stop_power_monitoring};
 use async_trait::async_trait;
 use postgres_types::{Json, Type};
 use serde_json::{json, Value};
 use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::pin::pin;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 use tokio_postgres::binary_copy::BinaryCopyInWriter;
 use tokio_postgres::{Client, NoTls};
 
@@ -14,7 +15,17 @@ use super::database::Database;
 
 pub struct PostgreSQL {
     client: Option<Arc<Mutex<Client>>>,
-    _connection: Option<tokio::task::JoinHandle<()>>,
+    _connection: Option<JoinHandle<()>>,
+    cgroup: Option<PathBuf>,
+    workload: Option<PostgresWorkload>,
+}
+
+/// Statements and worker connections opened by `prepare_workload`, one connection per client.
+struct PostgresWorkload {
+    read_query: String,
+    write_query: String,
+    connections: Vec<Client>,
+    _connection_tasks: Vec<JoinHandle<()>>,
 }
 
 #[async_trait]
@@ -23,25 +34,24 @@ impl Database for PostgreSQL {
         "PostgreSQL"
     }
 
-    async fn connect(&mut self) -> Result<(), Box<dyn Error>> {
-        let (client, connection) = tokio_postgres::connect(
-            "host=localhost user=postgres password=postgres dbname=benchmark",
-            NoTls,
-        )
-        .await?;
+    fn server_processes(&self) -> &'static [&'static str] {
+        &["postgres"]
+    }
 
-        let connection_task = tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("PostgreSQL connection error: {}", e);
-            }
-        });
+    fn cgroup(&self) -> Option<&Path> {
+        self.cgroup.as_deref()
+    }
 
+    async fn connect(&mut self) -> Result<(), Box<dyn Error>> {
+        let (client, connection_task) = open_connection().await?;
         self.client = Some(Arc::new(Mutex::new(client)));
         self._connection = Some(connection_task);
         Ok(())
     }
 
     async fn disconnect(&mut self) -> Result<(), Box<dyn Error>> {
+        // Dropping the worker clients closes their connections.
+        self.workload = None;
         if let Some(handle) = self._connection.take() {
             handle.abort();
         }
@@ -52,6 +62,12 @@ impl Database for PostgreSQL {
         Ok(())
     }
 
+    async fn server_version(&self) -> Result<String, Box<dyn Error>> {
+        let client = self.client.as_ref().ok_or("PostgreSQL client is not connected")?;
+        let row = client.lock().await.query_one("SELECT version()", &[]).await?;
+        Ok(row.get(0))
+    }
+
     async fn clean_database(&self) -> Result<(), Box<dyn Error>> {
         if let Some(client) = &self.client {
             let client = client.lock().await;
@@ -88,36 +104,89 @@ impl Database for PostgreSQL {
         Ok(())
     }
 
-    async fn run_queries(
-        &self,
-        depth: u8,
-        num_reads: usize,
-        num_writes: usize,
-        use_index: bool,
-        num_docs: usize,
-        measurement_name: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    async fn prepare_indexes(&self, _: u8) -> Result<(), Box<dyn Error>> {
         if let Some(client) = &self.client {
-            if use_index {
-                self.create_index().await?;
-            }
-
             let client = client.lock().await;
 
-            let mut pcm_process = start_power_monitoring(measurement_name);
-            if num_reads > num_writes {
-                self.run_read_heavy(num_reads, num_writes, depth, num_docs, &client)
-                    .await?;
-            } else {
-                self.run_write_heavy(num_reads, num_writes, depth, num_docs, &client)
-                    .await?;
-            }
-            stop_power_monitoring(&mut pcm_process);
+            client.execute(
+                "CREATE INDEX IF NOT EXISTS idx_gin_data ON hierarchical_data USING GIN (data jsonb_path_ops)",
+                &[],
+            ).await?;
         }
+
+        Ok(())
+    }
+
+    async fn prepare_workload(
+        &mut self,
+        depth: u8,
+        _: usize,
+        clients: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        // Close the connections of the previous workload before opening new ones.
+        self.workload = None;
+
+        let mut connections = Vec::with_capacity(clients);
+        let mut connection_tasks = Vec::with_capacity(clients);
+        for _ in 0..clients {
+            let (client, connection_task) = open_connection().await?;
+            connections.push(client);
+            connection_tasks.push(connection_task);
+        }
+
+        self.workload = Some(PostgresWorkload {
+            read_query: self.create_read_query(depth),
+            write_query: self.create_write_query(depth),
+            connections,
+            _connection_tasks: connection_tasks,
+        });
+        Ok(())
+    }
+
+    async fn execute_read(&self, client: usize) -> Result<(), Box<dyn Error>> {
+        let workload = self.workload.as_ref().ok_or("PostgreSQL workload was not prepared")?;
+        let client = workload.client(client)?;
+        let _rows = client.query(&workload.read_query, &[]).await?;
+        Ok(())
+    }
+
+    async fn execute_write(&self, client: usize) -> Result<(), Box<dyn Error>> {
+        let workload = self.workload.as_ref().ok_or("PostgreSQL workload was not prepared")?;
+        let client = workload.client(client)?;
+        client.execute(&workload.write_query, &[]).await?;
         Ok(())
     }
 }
 
+async fn open_connection() -> Result<(Client, JoinHandle<()>), Box<dyn Error>> {
+    let (client, connection) = tokio_postgres::connect(
+        "host=localhost user=postgres password=postgres dbname=benchmark",
+        NoTls,
+    )
+    .await?;
+
+    let connection_task = tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("PostgreSQL connection error: {}", e);
+        }
+    });
+    Ok((client, connection_task))
+}
+
+impl PostgresWorkload {
+    fn client(&self, index: usize) -> Result<&Client, Box<dyn Error>> {
+        self.connections
+            .get(index)
+            .ok_or_else(|| format!("PostgreSQL client {} was not opened", index).into())
+    }
+}
+
+impl Default for PostgreSQL {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PostgreSQL {
     async fn create_table(&self) -> Result<(), Box<dyn Error>> {
         if let Some(client) = &self.client {
@@ -137,9 +206,16 @@ impl PostgreSQL {
         PostgreSQL {
             client: None,
             _connection: None,
+            cgroup: None,
+            workload: None,
         }
     }
 
+    pub fn with_cgroup(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cgroup = Some(path.into());
+        self
+    }
+
     fn create_read_query(&self, depth: u8) -> String {
         let target_json = self.create_containment_json(depth);
         let read_path = self.create_read_path(depth);
@@ -166,64 +242,6 @@ impl PostgreSQL {
         )
     }
 
-    async fn run_read_heavy(
-        &self,
-        num_reads: usize,
-        num_writes: usize,
-        depth: u8,
-        _num_docs: usize,
-        client: &Client,
-    ) -> Result<(), Box<dyn Error>> {
-        let ratio = num_reads / num_writes;
-        let write_query = self.create_write_query(depth);
-        let read_query = self.create_read_query(depth);
-
-        for _ in 0..num_writes {
-            for _ in 0..ratio {
-                let _rows = client.query(&read_query, &[]).await?;
-            }
-
-            client.execute(&write_query, &[]).await?;
-        }
-
-        Ok(())
-    }
-
-    async fn run_write_heavy(
-        &self,
-        num_reads: usize,
-        num_writes: usize,
-        depth: u8,
-        _num_docs: usize,
-        client: &Client,
-    ) -> Result<(), Box<dyn Error>> {
-        let ratio = num_writes / num_reads;
-        let write_query = self.create_write_query(depth);
-        let read_query = self.create_read_query(depth);
-
-        for _ in 0..num_reads {
-            for _ in 0..ratio {
-                client.execute(&write_query, &[]).await?;
-            }
-
-            let _rows = client.query(&read_query, &[]).await?;
-        }
-        Ok(())
-    }
-
-    async fn create_index(&self) -> Result<(), Box<dyn Error>> {
-        if let Some(client) = &self.client {
-            let client = client.lock().await;
-
-            client.execute(
-                "CREATE INDEX IF NOT EXISTS idx_gin_data ON hierarchical_data USING GIN (data jsonb_path_ops)",
-                &[],
-            ).await?;
-        }
-
-        Ok(())
-    }
-
     fn create_containment_json(&self, depth: u8) -> Value {
         let mut value = json!({});
         let mut current = &mut value;
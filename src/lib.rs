@@ -1,6 +1,10 @@
 pub mod benchmark;
+pub mod cli;
 pub mod databases;
 pub mod metrics;
 
-pub use benchmark::{data_generator, harness};
+pub use benchmark::{
+    analysis, checkpoint, data_generator, harness, manifest, output, plan, results, runner,
+    workload,
+};
 pub use databases::{mongodb, postgres};
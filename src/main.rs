@@ -1,70 +1,272 @@
 use std::error::Error;
+use std::path::Path;
 
-use energy_benchmark::databases::database::Database;
-use energy_benchmark::harness::{
-    clean_and_insert_data, measure_idle_energy_consumption, run_read_heavy, run_write_heavy,
-};
-use energy_benchmark::mongodb::MongoDB;
-use energy_benchmark::postgres::PostgreSQL;
+use energy_benchmark::analysis::analyze_paths;
+use energy_benchmark::checkpoint::{Checkpoint, STATE_FILE_NAME};
+use energy_benchmark::cli::{parse_args, Command, GenerateArgs, RunArgs, USAGE};
+use energy_benchmark::data_generator;
+use energy_benchmark::databases::database::DATABASE_NAMES;
+use energy_benchmark::harness::Interrupt;
+use energy_benchmark::manifest::RunManifest;
+use energy_benchmark::metrics::{create_monitor, MONITOR_NAMES};
+use energy_benchmark::output::{create_run_dir, latest_resumable_run, run_dirs, INDEX_FILE_NAME};
+use energy_benchmark::plan::BenchmarkPlan;
+use energy_benchmark::results::Results;
+use energy_benchmark::runner::{describe_plan, describe_workload, order_seed, run_plan};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    println!("Main started");
-    let mut databases: Vec<Box<dyn Database>> =
-        vec![Box::new(MongoDB::new()), Box::new(PostgreSQL::new())];
-
-    println!("Databases initialized");
-
-    let depths = [3, 5, 10];
-    let epochs = [1, 2, 3];
-    let num_docs = 3000;
-
-    for epoch in epochs {
-        println!("Control Test C1");
-        measure_idle_energy_consumption(epoch).await?;
-        for db in databases.iter_mut() {
-            for depth in &depths {
-                clean_and_insert_data(db.as_mut(), *depth).await?;
-                let duration = run_read_heavy(db.as_ref(), *depth, false, num_docs, epoch).await?;
-                println!(
-                    "Test 1A - {} Depth {}: Epoch: {} {}ms",
-                    db.name(),
-                    depth,
-                    epoch,
-                    duration
-                );
-
-                let duration: u128 =
-                    run_write_heavy(db.as_ref(), *depth, false, num_docs, epoch).await?;
-                println!(
-                    "Test 1B - {} Depth {}: Epoch: {} {}ms",
-                    db.name(),
-                    depth,
-                    epoch,
-                    duration
-                );
-
-                clean_and_insert_data(db.as_mut(), *depth).await?;
-                let duration = run_read_heavy(db.as_ref(), *depth, true, num_docs, epoch).await?;
-                println!(
-                    "Test 2A - {} Depth {}: Epoch: {} {}ms",
-                    db.name(),
-                    depth,
-                    epoch,
-                    duration
-                );
-
-                let duration = run_write_heavy(db.as_ref(), *depth, true, num_docs, epoch).await?;
-                println!(
-                    "Test 2B - {} Depth {}: Epoch: {} {}ms",
-                    db.name(),
-                    depth,
-                    epoch,
-                    duration
-                );
+async fn main() {
+    if let Err(e) = dispatch().await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn dispatch() -> Result<(), Box<dyn Error>> {
+    match parse_args(std::env::args().skip(1))? {
+        Command::Run(args) => run(args).await,
+        Command::Generate(args) => generate(args),
+        Command::Analyze {
+            paths,
+            results_root,
+        } => analyze(&paths, &results_root),
+        Command::List { plan } => list(&plan),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+async fn run(args: RunArgs) -> Result<(), Box<dyn Error>> {
+    // Validate after filtering so datasets that were filtered out need not exist.
+    let mut plan = BenchmarkPlan::read(&args.plan)?.filtered(&args.filter)?;
+    if let Some(monitor) = args.monitor {
+        plan.monitor = monitor;
+    }
+    plan.validate()
+        .map_err(|e| format!("Invalid plan {}: {}", args.plan.display(), e))?;
+    println!("Plan loaded from {}", args.plan.display());
+
+    if args.dry_run {
+        for step in describe_plan(&plan) {
+            println!("{}", step);
+        }
+        return Ok(());
+    }
+
+    let results_dir = if args.resume {
+        latest_resumable_run(&args.results_root)?
+    } else {
+        create_run_dir(&args.results_root)?
+    };
+    println!("Results directory: {}", results_dir.display());
+    let state_file = results_dir.join(STATE_FILE_NAME);
+    let mut checkpoint = if args.resume {
+        Checkpoint::resume(&state_file, &plan)?
+    } else {
+        Checkpoint::create(&state_file, &plan, order_seed(&plan))?
+    };
+
+    let mut monitor = create_monitor(&plan.monitor, &results_dir)?;
+    println!("Power monitor: {}", monitor.name());
+
+    let mut manifest =
+        RunManifest::collect(&plan, checkpoint.order_seed(), &results_dir, args.resume).await?;
+    manifest.write()?;
+
+    let interrupt = Interrupt::install();
+    let results = run_plan(&plan, monitor.as_mut(), &mut checkpoint, &interrupt).await;
+    manifest.finish(
+        interrupt.is_set(),
+        results.as_ref().err().map(|e| e.to_string()),
+    );
+    manifest.write()?;
+    let results = results?;
+    print_results(&results);
+
+    results.write_summary(&results_dir, "results_summary.csv")?;
+    results.write_client_server_report(&results_dir, "client_server_split.csv")?;
+    results.write_latency_report(&results_dir, "latency_summary.csv")?;
+    results.write_failure_report(&results_dir, "failures.csv")?;
+    results.write_index(&results_dir, INDEX_FILE_NAME)?;
+    print_failures(&results);
+    if interrupt.is_set() {
+        println!("Run interrupted; partial results written. Continue it with --resume");
+    }
+    Ok(())
+}
+
+fn print_failures(results: &Results) {
+    let failures = results.failures();
+    if failures.is_empty() {
+        return;
+    }
+    println!(
+        "{} of {} test runs failed (rerun with --resume to retry them):",
+        failures.len(),
+        failures.len() + results.runs().len()
+    );
+    for failure in failures {
+        println!(
+            "Test {} - {} Depth {}: Epoch: {} failed after {} attempt(s): {}",
+            failure.test_id,
+            failure.database,
+            failure.depth,
+            failure.epoch,
+            failure.attempts,
+            failure.error
+        );
+    }
+}
+
+fn print_results(results: &Results) {
+    for summary in results.summaries() {
+        println!(
+            "Test {} - {} Depth {}: Epoch: {} gross {:.2} J, net {:.2} J{}",
+            summary.test_id,
+            summary.database,
+            summary.depth,
+            summary.epoch,
+            summary.gross_joules.unwrap_or(f64::NAN),
+            summary.net_joules.unwrap_or(f64::NAN),
+            if summary.thermal_drift {
+                " (thermal/frequency drift)"
+            } else {
+                ""
             }
-            db.disconnect().await?;
+        );
+    }
+    for summary in results.summaries() {
+        if summary.stop_condition != "operations" {
+            println!(
+                "Test {} - {} Depth {}: Epoch: {} {} operations until {} bound, {:.2} ops/J net",
+                summary.test_id,
+                summary.database,
+                summary.depth,
+                summary.epoch,
+                summary.operations,
+                summary.stop_condition,
+                summary.ops_per_net_joule.unwrap_or(f64::NAN)
+            );
+        }
+        if let Some(target_rate) = summary.target_rate {
+            println!(
+                "Test {} - {} Depth {}: Epoch: {} {:.1}/{:.1} ops/s, {} missed deadlines, {:.4} J/op net",
+                summary.test_id,
+                summary.database,
+                summary.depth,
+                summary.epoch,
+                summary.achieved_ops_per_sec.unwrap_or(f64::NAN),
+                target_rate,
+                summary.missed_deadlines.unwrap_or(0),
+                summary.net_joules_per_op.unwrap_or(f64::NAN)
+            );
+        }
+    }
+    for split in results.client_server_splits() {
+        println!(
+            "Test {} - {} Depth {}: Epoch: {} client {:.2} J, server {:.2} J ({} energy, {:.1}% client)",
+            split.test_id,
+            split.database,
+            split.depth,
+            split.epoch,
+            split.client_joules,
+            split.server_joules,
+            split.energy_basis,
+            split.client_share * 100.0
+        );
+    }
+    for report in results.latency_reports() {
+        println!(
+            "Test {} - {} Depth {}: {} x{} p50 {}us, p90 {}us, p99 {}us, p99.9 {}us, max {}us",
+            report.test_id,
+            report.database,
+            report.depth,
+            report.operation,
+            report.count,
+            report.p50_us,
+            report.p90_us,
+            report.p99_us,
+            report.p999_us,
+            report.max_us
+        );
+    }
+}
+
+fn generate(args: GenerateArgs) -> Result<(), Box<dyn Error>> {
+    for depth in args.depths {
+        if args.dry_run {
+            println!(
+                "Would generate {} documents for depth {}",
+                args.documents, depth
+            );
+            continue;
+        }
+        data_generator::generate_and_save_data(depth, args.documents)?;
+        println!("Generated {} documents for depth {}", args.documents, depth);
+    }
+    Ok(())
+}
+
+fn analyze(paths: &[std::path::PathBuf], results_root: &Path) -> Result<(), Box<dyn Error>> {
+    let paths = if paths.is_empty() {
+        let latest = run_dirs(results_root)?
+            .pop()
+            .ok_or_else(|| format!("No runs under {}", results_root.display()))?;
+        vec![latest]
+    } else {
+        paths.to_vec()
+    };
+    let files = analyze_paths(&paths)?;
+    if files.is_empty() {
+        println!(
+            "Nothing to analyze in {}: only runs with the pcm monitor write measurement files",
+            paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    for file in files {
+        match file.summary {
+            Ok(summary) => println!(
+                "{}: {} samples over {:.2}s, package {:.2} J, DRAM {:.2} J, mean {:.2} W",
+                file.path.display(),
+                summary.samples,
+                summary.duration.as_secs_f64(),
+                summary.package_joules,
+                summary.dram_joules,
+                summary.mean_watts
+            ),
+            Err(e) => println!("{}: skipped ({})", file.path.display(), e),
         }
     }
     Ok(())
 }
+
+fn list(plan_path: &Path) -> Result<(), Box<dyn Error>> {
+    println!("Databases: {}", DATABASE_NAMES.join(", "));
+    println!("Power monitors: {}", MONITOR_NAMES.join(", "));
+
+    // Listing should still work before the datasets have been generated.
+    let plan = BenchmarkPlan::read(plan_path)?;
+    println!("Test cases in {}:", plan_path.display());
+    for test in &plan.test_cases {
+        println!(
+            "  {} {} ({} on {} client(s){}){}",
+            test.id,
+            test.name,
+            describe_workload(test),
+            test.clients,
+            if test.use_index { ", indexed" } else { "" },
+            if test.description.is_empty() {
+                String::new()
+            } else {
+                format!(" - {}", test.description)
+            }
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,291 @@
+use super::sampler::{Sampled, Sampler};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use sysinfo::{PidExt, ProcessExt, ProcessRefreshKind, System, SystemExt};
+
+const PROC_ROOT: &str = "/proc";
+
+/// CPU seconds spent during a measurement window, grouped by who spent them.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CpuShares {
+    pub server_secs: f64,
+    pub client_secs: f64,
+    pub other_secs: f64,
+}
+
+impl CpuShares {
+    pub fn total_secs(&self) -> f64 {
+        self.server_secs + self.client_secs + self.other_secs
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct EnergyAttribution {
+    pub cpu: CpuShares,
+    pub server_joules: f64,
+    pub client_joules: f64,
+    pub other_joules: f64,
+}
+
+impl EnergyAttribution {
+    /// Splits package energy in proportion to CPU time. A window without any
+    /// recorded CPU time is attributed entirely to "other".
+    pub fn apportion(cpu: CpuShares, package_joules: f64) -> Self {
+        let total = cpu.total_secs();
+        if total <= 0.0 {
+            return EnergyAttribution {
+                cpu,
+                server_joules: 0.0,
+                client_joules: 0.0,
+                other_joules: package_joules,
+            };
+        }
+
+        EnergyAttribution {
+            cpu,
+            server_joules: package_joules * cpu.server_secs / total,
+            client_joules: package_joules * cpu.client_secs / total,
+            other_joules: package_joules * cpu.other_secs / total,
+        }
+    }
+}
+
+/// Linux reports CPU times in `/proc` in USER_HZ ticks, which is 100 on every architecture.
+const TICKS_PER_SEC: f64 = 100.0;
+
+/// CPU ticks of the server processes and of this process, read from `/proc/<pid>/stat`,
+/// and of the whole machine, read from `/proc/stat`.
+///
+/// Only these few files are read per sample, so the sampler barely adds to the client's own
+/// CPU time. Server processes are looked up once when the window opens; ones started later,
+/// and ones that exit, count towards "other" for the time they aren't tracked.
+struct ProcessTrace {
+    proc_root: PathBuf,
+    /// Each server process with its last reading, or `None` once it has exited.
+    servers: Vec<(u32, Option<u64>)>,
+    client_pid: u32,
+    client_ticks: u64,
+    busy_ticks: u64,
+    shares: CpuShares,
+}
+
+impl ProcessTrace {
+    fn open(server_processes: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let mut system = System::new();
+        system.refresh_processes_specifics(ProcessRefreshKind::new());
+        let server_pids = system
+            .processes()
+            .iter()
+            .filter(|(_, process)| server_processes.contains(&process.name()))
+            .map(|(pid, _)| pid.as_u32())
+            .collect();
+
+        Ok(Self::open_with_root(
+            Path::new(PROC_ROOT),
+            server_pids,
+            std::process::id(),
+        )?)
+    }
+
+    fn open_with_root(
+        proc_root: &Path,
+        server_pids: Vec<u32>,
+        client_pid: u32,
+    ) -> io::Result<Self> {
+        let servers = server_pids
+            .into_iter()
+            .map(|pid| (pid, process_ticks(proc_root, pid).ok()))
+            .collect();
+
+        Ok(ProcessTrace {
+            servers,
+            client_pid,
+            client_ticks: process_ticks(proc_root, client_pid)?,
+            busy_ticks: busy_ticks(proc_root)?,
+            proc_root: proc_root.to_path_buf(),
+            shares: CpuShares::default(),
+        })
+    }
+}
+
+impl Sampled for ProcessTrace {
+    fn sample(&mut self) -> io::Result<()> {
+        let mut server_ticks = 0;
+        for (pid, last) in &mut self.servers {
+            let Some(previous) = *last else {
+                continue;
+            };
+            *last = process_ticks(&self.proc_root, *pid).ok();
+            server_ticks += last.map_or(0, |now| now.saturating_sub(previous));
+        }
+
+        let client_now = process_ticks(&self.proc_root, self.client_pid)?;
+        let client_ticks = client_now.saturating_sub(self.client_ticks);
+        self.client_ticks = client_now;
+
+        let busy_now = busy_ticks(&self.proc_root)?;
+        let busy = busy_now.saturating_sub(self.busy_ticks);
+        self.busy_ticks = busy_now;
+
+        // The counters are read a moment apart, so the processes may have a tick more than the machine.
+        let other_ticks = busy.saturating_sub(server_ticks + client_ticks);
+        self.shares.server_secs += server_ticks as f64 / TICKS_PER_SEC;
+        self.shares.client_secs += client_ticks as f64 / TICKS_PER_SEC;
+        self.shares.other_secs += other_ticks as f64 / TICKS_PER_SEC;
+        Ok(())
+    }
+}
+
+/// User plus system ticks of process `pid`, including all its threads.
+fn process_ticks(proc_root: &Path, pid: u32) -> io::Result<u64> {
+    let stat = fs::read_to_string(proc_root.join(pid.to_string()).join("stat"))?;
+    // The command name may contain spaces and parentheses, so fields are counted from its end.
+    let fields: Vec<&str> = stat
+        .rsplit_once(')')
+        .map(|(_, rest)| rest.split_whitespace().collect())
+        .unwrap_or_default();
+    match (fields.get(11), fields.get(12)) {
+        (Some(utime), Some(stime)) => Ok(parse_ticks(utime)? + parse_ticks(stime)?),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unexpected /proc/{}/stat: {}", pid, stat.trim()),
+        )),
+    }
+}
+
+/// Non-idle ticks of all CPUs together: every column of the `cpu` line of `/proc/stat` except
+/// idle and iowait. Guest time is already part of user time.
+fn busy_ticks(proc_root: &Path) -> io::Result<u64> {
+    let stat = fs::read_to_string(proc_root.join("stat"))?;
+    let line = stat
+        .lines()
+        .find(|line| line.starts_with("cpu "))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No cpu line in /proc/stat"))?;
+    let mut busy = 0;
+    for (column, value) in line.split_whitespace().skip(1).take(8).enumerate() {
+        // user, nice, system, idle, iowait, irq, softirq, steal
+        if column != 3 && column != 4 {
+            busy += parse_ticks(value)?;
+        }
+    }
+    Ok(busy)
+}
+
+fn parse_ticks(value: &str) -> io::Result<u64> {
+    value
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", value, e)))
+}
+
+/// Samples per-process CPU time in the background while a measurement window is open.
+pub struct ProcessSampler {
+    sampler: Sampler<ProcessTrace>,
+}
+
+impl ProcessSampler {
+    pub fn start(server_processes: &[&str], interval: Duration) -> Result<Self, Box<dyn Error>> {
+        let trace = ProcessTrace::open(server_processes)?;
+        Ok(ProcessSampler {
+            sampler: Sampler::start(trace, interval),
+        })
+    }
+
+    pub async fn stop(self) -> Result<CpuShares, Box<dyn Error>> {
+        Ok(self.sampler.stop().await?.shares)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shares(server_secs: f64, client_secs: f64, other_secs: f64) -> CpuShares {
+        CpuShares {
+            server_secs,
+            client_secs,
+            other_secs,
+        }
+    }
+
+    #[test]
+    fn apportion_splits_the_whole_package_energy_by_cpu_time() {
+        let attribution = EnergyAttribution::apportion(shares(3.0, 1.0, 0.0), 100.0);
+        assert_eq!(attribution.server_joules, 75.0);
+        assert_eq!(attribution.client_joules, 25.0);
+        assert_eq!(attribution.other_joules, 0.0);
+
+        let attribution = EnergyAttribution::apportion(shares(0.7, 0.2, 0.4), 42.0);
+        let total =
+            attribution.server_joules + attribution.client_joules + attribution.other_joules;
+        assert!((total - 42.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn apportion_without_cpu_time_attributes_everything_to_other() {
+        let attribution = EnergyAttribution::apportion(CpuShares::default(), 12.5);
+        assert_eq!(attribution.server_joules, 0.0);
+        assert_eq!(attribution.client_joules, 0.0);
+        assert_eq!(attribution.other_joules, 12.5);
+    }
+
+    fn write_process(root: &Path, pid: u32, comm: &str, utime: u64, stime: u64) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("stat"),
+            format!(
+                "{} ({}) S 1 1 1 0 -1 4194560 100 0 0 0 {} {} 0 0 20 0 4 0 100 0 0\n",
+                pid, comm, utime, stime
+            ),
+        )
+        .unwrap();
+    }
+
+    fn write_stat(root: &Path, user: u64, system: u64, idle: u64) {
+        fs::write(
+            root.join("stat"),
+            format!(
+                "cpu  {} 0 {} {} 50 0 0 0 0 0\ncpu0 0 0 0 0 0 0 0 0 0 0\n",
+                user, system, idle
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reads_process_ticks_after_the_command_name() {
+        let root = std::env::temp_dir().join(format!("proc_comm_{}", std::process::id()));
+        write_process(&root, 7, "odd) name", 120, 30);
+        let ticks = process_ticks(&root, 7);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(ticks.unwrap(), 150);
+    }
+
+    #[test]
+    fn samples_server_client_and_other_ticks() {
+        let root = std::env::temp_dir().join(format!("proc_trace_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        write_process(&root, 10, "postgres", 100, 0);
+        write_process(&root, 11, "postgres", 100, 0);
+        write_process(&root, 20, "energy_benchmark", 50, 0);
+        write_stat(&root, 1000, 200, 5000);
+        let mut trace = ProcessTrace::open_with_root(&root, vec![10, 11], 20).unwrap();
+
+        write_process(&root, 10, "postgres", 150, 50);
+        // The second backend exits during the window.
+        fs::remove_dir_all(root.join("11")).unwrap();
+        write_process(&root, 20, "energy_benchmark", 80, 20);
+        write_stat(&root, 1300, 300, 9000);
+        trace.sample().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(trace.shares.server_secs, 1.0);
+        assert_eq!(trace.shares.client_secs, 0.5);
+        assert_eq!(trace.shares.other_secs, 2.5);
+    }
+}
@@ -0,0 +1,139 @@
+use super::sampler::{Sampled, Sampler};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Resource usage of a cgroup over one measurement window.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CgroupUsage {
+    pub cpu_usec: u64,
+    pub read_bytes: u64,
+    pub written_bytes: u64,
+    pub peak_memory_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CgroupSnapshot {
+    usage_usec: u64,
+    read_bytes: u64,
+    written_bytes: u64,
+    memory_current: u64,
+}
+
+/// Relative paths are resolved against the unified hierarchy mount point.
+pub fn resolve_cgroup_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        Path::new(CGROUP_ROOT).join(path)
+    }
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Value of `key` in a flat keyed file such as `cpu.stat`.
+fn keyed_value(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once(' ')?;
+        (name == key).then(|| value.trim().parse().ok())?
+    })
+}
+
+/// Sums `rbytes` and `wbytes` over every device line of `io.stat`.
+fn io_bytes(content: &str) -> (u64, u64) {
+    let mut read = 0;
+    let mut written = 0;
+    for field in content.split_whitespace() {
+        if let Some((key, value)) = field.split_once('=') {
+            let value: u64 = value.parse().unwrap_or(0);
+            match key {
+                "rbytes" => read += value,
+                "wbytes" => written += value,
+                _ => {}
+            }
+        }
+    }
+    (read, written)
+}
+
+fn read_memory_current(path: &Path) -> io::Result<u64> {
+    Ok(read_optional(&path.join("memory.current"))?
+        .and_then(|content| content.trim().parse().ok())
+        .unwrap_or(0))
+}
+
+// The io and memory controllers may not be enabled for the cgroup; their files are then absent.
+fn read_snapshot(path: &Path) -> io::Result<CgroupSnapshot> {
+    let cpu_stat = fs::read_to_string(path.join("cpu.stat"))?;
+    let (read_bytes, written_bytes) = read_optional(&path.join("io.stat"))?
+        .map(|content| io_bytes(&content))
+        .unwrap_or_default();
+
+    Ok(CgroupSnapshot {
+        usage_usec: keyed_value(&cpu_stat, "usage_usec").unwrap_or(0),
+        read_bytes,
+        written_bytes,
+        memory_current: read_memory_current(path)?,
+    })
+}
+
+/// Snapshots `cpu.stat` and `io.stat` at the edges of a window and tracks
+/// `memory.current` in between to find the window's peak memory.
+pub struct CgroupCollector {
+    start: CgroupSnapshot,
+    path: PathBuf,
+    sampler: Sampler<PeakMemory>,
+}
+
+struct PeakMemory {
+    path: PathBuf,
+    bytes: u64,
+}
+
+impl Sampled for PeakMemory {
+    fn sample(&mut self) -> io::Result<()> {
+        self.bytes = self.bytes.max(read_memory_current(&self.path)?);
+        Ok(())
+    }
+}
+
+impl CgroupCollector {
+    pub fn start(path: &Path, interval: Duration) -> Result<Self, Box<dyn Error>> {
+        let path = resolve_cgroup_path(path);
+        let start = read_snapshot(&path)
+            .map_err(|e| format!("Failed to read cgroup {}: {}", path.display(), e))?;
+        let peak_memory = PeakMemory {
+            path: path.clone(),
+            bytes: start.memory_current,
+        };
+
+        Ok(CgroupCollector {
+            start,
+            path,
+            sampler: Sampler::start(peak_memory, interval),
+        })
+    }
+
+    pub async fn stop(self) -> Result<CgroupUsage, Box<dyn Error>> {
+        let peak_memory = self.sampler.stop().await?.bytes;
+        let end = read_snapshot(&self.path)?;
+
+        Ok(CgroupUsage {
+            cpu_usec: end.usage_usec.saturating_sub(self.start.usage_usec),
+            read_bytes: end.read_bytes.saturating_sub(self.start.read_bytes),
+            written_bytes: end.written_bytes.saturating_sub(self.start.written_bytes),
+            peak_memory_bytes: peak_memory.max(end.memory_current),
+        })
+    }
+}
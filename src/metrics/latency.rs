@@ -0,0 +1,108 @@
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::time::Duration;
+
+/// Highest latency the histograms can hold; slower operations are clamped to it.
+const MAX_LATENCY_US: u64 = 3_600_000_000;
+const SIGNIFICANT_DIGITS: u8 = 3;
+
+/// Per-operation latency of the reads and writes of one workload, in microseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationLatencies {
+    #[serde(serialize_with = "serialize_histogram")]
+    #[serde(deserialize_with = "deserialize_histogram")]
+    pub reads: Histogram<u64>,
+    #[serde(serialize_with = "serialize_histogram")]
+    #[serde(deserialize_with = "deserialize_histogram")]
+    pub writes: Histogram<u64>,
+}
+
+impl OperationLatencies {
+    pub fn new() -> Self {
+        OperationLatencies {
+            reads: new_histogram(),
+            writes: new_histogram(),
+        }
+    }
+
+    pub fn record_read(&mut self, latency: Duration) {
+        record(&mut self.reads, latency);
+    }
+
+    pub fn record_write(&mut self, latency: Duration) {
+        record(&mut self.writes, latency);
+    }
+
+    /// Adds every operation recorded in `other`, e.g. to combine epochs.
+    pub fn merge(&mut self, other: &OperationLatencies) {
+        // Both sides share the same bounds, so adding can't fail.
+        let _ = self.reads.add(&other.reads);
+        let _ = self.writes.add(&other.writes);
+    }
+}
+
+impl Default for OperationLatencies {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn new_histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, MAX_LATENCY_US, SIGNIFICANT_DIGITS)
+        .expect("latency histogram bounds are valid")
+}
+
+fn record(histogram: &mut Histogram<u64>, latency: Duration) {
+    let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+    histogram.saturating_record(micros.max(1));
+}
+
+/// Stores a histogram as its recorded `[value, count]` pairs.
+fn serialize_histogram<S: Serializer>(
+    histogram: &Histogram<u64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        histogram
+            .iter_recorded()
+            .map(|bucket| (bucket.value_iterated_to(), bucket.count_at_value())),
+    )
+}
+
+fn deserialize_histogram<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Histogram<u64>, D::Error> {
+    let mut histogram = new_histogram();
+    for (value, count) in Vec::<(u64, u64)>::deserialize(deserializer)? {
+        histogram.saturating_record_n(value, count);
+    }
+    Ok(histogram)
+}
+
+/// Tail latency percentiles of one operation type, in microseconds.
+#[derive(Debug, Clone, Copy)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub p999_us: u64,
+    pub max_us: u64,
+}
+
+impl LatencySummary {
+    /// Returns `None` when no operation was recorded.
+    pub fn of(histogram: &Histogram<u64>) -> Option<Self> {
+        if histogram.is_empty() {
+            return None;
+        }
+        Some(LatencySummary {
+            count: histogram.len(),
+            p50_us: histogram.value_at_quantile(0.5),
+            p90_us: histogram.value_at_quantile(0.9),
+            p99_us: histogram.value_at_quantile(0.99),
+            p999_us: histogram.value_at_quantile(0.999),
+            max_us: histogram.max(),
+        })
+    }
+}
@@ -1,2 +1,16 @@
+pub mod attribution;
+pub mod cgroup;
+pub mod latency;
+pub mod monitor;
 pub mod pcm;
-pub use pcm::{start_power_monitoring, stop_power_monitoring};
+pub mod pcm_csv;
+pub mod rapl;
+pub mod sampler;
+pub mod simulated;
+pub mod thermal;
+pub use attribution::{EnergyAttribution, ProcessSampler};
+pub use cgroup::{CgroupCollector, CgroupUsage};
+pub use latency::{LatencySummary, OperationLatencies};
+pub use monitor::{create_monitor, EnergySample, Measurement, PowerMonitor, MONITOR_NAMES};
+pub use pcm::pcm_version;
+pub use thermal::{current_governors, DriftThresholds, ThermalSampler, ThermalTelemetry};
@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::attribution::EnergyAttribution;
+use super::cgroup::CgroupUsage;
+use super::latency::OperationLatencies;
+use super::pcm::PcmMonitor;
+use super::rapl::RaplMonitor;
+use super::simulated::SimulatedMonitor;
+use super::thermal::ThermalTelemetry;
+
+/// Energy consumed during one sampling interval, ending `elapsed` after the window opened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergySample {
+    pub elapsed: Duration,
+    pub package_joules: f64,
+    pub core_joules: Option<f64>,
+    pub dram_joules: Option<f64>,
+}
+
+/// Result of a single measurement window opened by the harness.
+///
+/// `started_at` and `ended_at` are the effective boundaries of the window the
+/// energy figures cover, which may differ from when `start`/`stop` were called.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Measurement {
+    pub name: String,
+    pub monitor: String,
+    pub started_at: SystemTime,
+    pub ended_at: SystemTime,
+    pub duration: Duration,
+    pub package_joules: Option<f64>,
+    pub core_joules: Option<f64>,
+    pub dram_joules: Option<f64>,
+    pub samples: Vec<EnergySample>,
+    pub output_file: Option<PathBuf>,
+    /// Split of `package_joules` between database server, benchmark client and everything else.
+    pub attribution: Option<EnergyAttribution>,
+    /// Resource usage of the database server's cgroup during the window.
+    pub cgroup: Option<CgroupUsage>,
+    pub thermal: Option<ThermalTelemetry>,
+    /// Latency of every read and write the workload ran inside the window.
+    pub latency: Option<OperationLatencies>,
+    /// Open-loop operations that could not start at their scheduled time.
+    pub missed_deadlines: Option<u64>,
+    /// The window was cut short by an interrupt, so its figures cover only part of the work.
+    #[serde(default)]
+    pub aborted: bool,
+}
+
+impl Measurement {
+    pub fn new(
+        name: String,
+        monitor: &str,
+        started_at: SystemTime,
+        duration: Duration,
+    ) -> Self {
+        Measurement {
+            name,
+            monitor: monitor.to_string(),
+            started_at,
+            ended_at: started_at + duration,
+            duration,
+            package_joules: None,
+            core_joules: None,
+            dram_joules: None,
+            samples: Vec::new(),
+            output_file: None,
+            attribution: None,
+            cgroup: None,
+            thermal: None,
+            latency: None,
+            missed_deadlines: None,
+            aborted: false,
+        }
+    }
+
+    pub fn mean_watts(&self) -> Option<f64> {
+        let secs = self.duration.as_secs_f64();
+        match self.package_joules {
+            Some(joules) if secs > 0.0 => Some(joules / secs),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+pub trait PowerMonitor: Send {
+    fn name(&self) -> &'static str;
+    async fn start(&mut self, measurement_name: &str) -> Result<(), Box<dyn Error>>;
+    async fn stop(&mut self) -> Result<Measurement, Box<dyn Error>>;
+    /// Package joules measured since `start`, for monitors that can report them while running.
+    fn energy_so_far(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Names accepted by [`create_monitor`].
+pub const MONITOR_NAMES: &[&str] = &["pcm", "rapl", "simulated", "simulated-fixed"];
+
+/// Builds the power monitor registered under `name`. Monitors that write a file per
+/// measurement window write it into `output_dir`.
+pub fn create_monitor(
+    name: &str,
+    output_dir: &Path,
+) -> Result<Box<dyn PowerMonitor>, Box<dyn Error>> {
+    match name {
+        "pcm" => Ok(Box::new(PcmMonitor::new(output_dir))),
+        "rapl" => Ok(Box::new(RaplMonitor::new())),
+        "simulated" => Ok(Box::new(SimulatedMonitor::cpu_time())),
+        "simulated-fixed" => Ok(Box::new(SimulatedMonitor::fixed(50.0))),
+        other => Err(format!("Unknown power monitor: {}", other).into()),
+    }
+}
@@ -1,29 +1,199 @@
-use std::{path::Path, process::{Child, Command, Stdio}};
+use super::monitor::{Measurement, PowerMonitor};
+use super::pcm_csv::{
+    parse_pcm_csv, parse_pcm_file, summarize, to_energy_samples, SAMPLE_INTERVAL,
+};
+use async_trait::async_trait;
 use fs_extra::dir::create_all;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::time::sleep;
 
-pub fn stop_power_monitoring(pcm_process: &mut Child) {
-    pcm_process.kill().expect("Failed to kill PCM");
-    let _ = pcm_process.wait();
+const HEADER_ROWS: usize = 2;
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+const FINAL_SAMPLE_TIMEOUT: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+struct ActiveMeasurement {
+    name: String,
+    file_path: PathBuf,
+    process: Child,
+    /// Data rows already written when the first sample was observed; they predate the workload.
+    ready_rows: usize,
+    started_at: SystemTime,
+    start_time: Instant,
+}
+
+pub struct PcmMonitor {
+    output_dir: PathBuf,
+    active: Option<ActiveMeasurement>,
+}
+
+#[async_trait]
+impl PowerMonitor for PcmMonitor {
+    fn name(&self) -> &'static str {
+        "pcm"
+    }
+
+    async fn start(&mut self, measurement_name: &str) -> Result<(), Box<dyn Error>> {
+        if self.active.is_some() {
+            return Err("PCM measurement already running".into());
+        }
+
+        if !Path::new(&self.output_dir).exists() {
+            create_all(&self.output_dir, false)?;
+        }
+
+        let file_path = self.output_dir.join(measurement_name);
+        // A stale file from an earlier run would satisfy the readiness check immediately.
+        if file_path.exists() {
+            fs::remove_file(&file_path)?;
+        }
+
+        let mut process = Command::new("pcm")
+            .arg(SAMPLE_INTERVAL.as_secs_f64().to_string())
+            .arg("-r")
+            .arg("-silent")
+            .arg(format!("-csv={}", file_path.display()))
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run Intel PCM command: {}", e))?;
+
+        let ready_rows = match wait_for_rows(&mut process, &file_path, 1, READY_TIMEOUT).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                let _ = process.kill();
+                let _ = process.wait();
+                return Err(format!("PCM did not produce a first sample: {}", e).into());
+            }
+        };
+
+        self.active = Some(ActiveMeasurement {
+            name: measurement_name.to_string(),
+            file_path,
+            process,
+            ready_rows,
+            started_at: SystemTime::now(),
+            start_time: Instant::now(),
+        });
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<Measurement, Box<dyn Error>> {
+        let mut active = self
+            .active
+            .take()
+            .ok_or("PCM measurement was not started")?;
+
+        // Wait for the sample covering the end of the workload before killing PCM,
+        // otherwise the last interval is cut off.
+        let rows_at_stop = completed_rows(&active.file_path)?;
+        let final_rows = wait_for_rows(
+            &mut active.process,
+            &active.file_path,
+            rows_at_stop + 1,
+            FINAL_SAMPLE_TIMEOUT,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("PCM did not flush a final sample: {}", e);
+            rows_at_stop
+        });
+        let duration = active.start_time.elapsed();
+        active.process.kill()?;
+        active.process.wait()?;
+
+        let mut measurement =
+            Measurement::new(active.name, self.name(), active.started_at, duration);
+
+        match parse_pcm_file(&active.file_path) {
+            Ok(samples) => {
+                let end = final_rows.min(samples.len());
+                let window = &samples[active.ready_rows.min(end)..end];
+                let summary = summarize(window);
+                if !window.is_empty() {
+                    measurement.duration = summary.duration;
+                    measurement.ended_at = measurement.started_at + summary.duration;
+                }
+                measurement.package_joules = Some(summary.package_joules);
+                measurement.dram_joules = Some(summary.dram_joules);
+                measurement.samples = to_energy_samples(window);
+            }
+            Err(e) => eprintln!("Failed to read PCM output: {}", e),
+        }
+        measurement.output_file = Some(active.file_path);
+
+        Ok(measurement)
+    }
+
+    fn energy_so_far(&self) -> Option<f64> {
+        let active = self.active.as_ref()?;
+        let content = fs::read_to_string(&active.file_path).ok()?;
+        // PCM may be halfway through writing a row; only parse complete ones.
+        let complete = &content[..content.rfind('\n').map_or(0, |end| end + 1)];
+        let samples = parse_pcm_csv(complete).ok()?;
+        let window = &samples[active.ready_rows.min(samples.len())..];
+        Some(summarize(window).package_joules)
+    }
+}
+
+/// First line of `pcm --version`, or `None` when PCM isn't installed.
+pub fn pcm_version() -> Option<String> {
+    let output = Command::new("pcm")
+        .arg("--version")
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    Some(line.to_string())
+}
+
+/// Number of complete data rows PCM has written so far.
+fn completed_rows(file_path: &Path) -> Result<usize, Box<dyn Error>> {
+    match fs::read(file_path) {
+        Ok(content) => {
+            let lines = content.iter().filter(|&&byte| byte == b'\n').count();
+            Ok(lines.saturating_sub(HEADER_ROWS))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn wait_for_rows(
+    process: &mut Child,
+    file_path: &Path,
+    rows: usize,
+    timeout: Duration,
+) -> Result<usize, Box<dyn Error>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let written = completed_rows(file_path)?;
+        if written >= rows {
+            return Ok(written);
+        }
+        if let Some(status) = process.try_wait()? {
+            return Err(format!("PCM exited with {}", status).into());
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("timed out after {:?}", timeout).into());
+        }
+        sleep(POLL_INTERVAL).await;
+    }
 }
 
-pub fn start_power_monitoring(file_name: &str) -> Child {
-    let project_root = env!("CARGO_MANIFEST_DIR");
-    let file_dir = format!("{}/src/energy_benchmarks/", project_root);
-    
-    if !Path::new(&file_dir).exists() {
-        let _ = create_all(&file_dir, false);
+impl PcmMonitor {
+    /// Writes PCM's CSV for each measurement window into `output_dir`.
+    pub fn new(output_dir: &Path) -> Self {
+        PcmMonitor {
+            output_dir: output_dir.to_path_buf(),
+            active: None,
+        }
     }
-    
-    let file_path = format!("{}{}", file_dir, file_name);
-    
-    let pcm_process = Command::new("pcm")
-    .arg("0.1")
-    .arg("-r")
-    .arg("-silent")
-    .arg(format!("-csv=\"{}\"", file_path))
-    .stdout(Stdio::piped())
-    .spawn()
-    .expect("Failed to run Intel PCM command");
-
-pcm_process
 }
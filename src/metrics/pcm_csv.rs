@@ -0,0 +1,409 @@
+use csv::{ReaderBuilder, StringRecord};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use super::monitor::EnergySample;
+
+const PACKAGE_ENERGY_COLUMN: &str = "Proc Energy (Joules)";
+const DRAM_ENERGY_COLUMN: &str = "DRAM Energy (Joules)";
+
+/// Interval the PCM monitor has `pcm` sample at. A single row can't show its own interval,
+/// so it is assumed to cover this long.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// One row of PCM's CSV output. Energy values cover the interval ending at `timestamp`.
+#[derive(Debug, Clone)]
+pub struct PcmSample {
+    /// Seconds since the Unix epoch, in the clock PCM printed (local time).
+    pub timestamp: f64,
+    pub package_joules: Vec<f64>,
+    pub dram_joules: Vec<f64>,
+    pub frequency: Option<f64>,
+    pub ipc: Option<f64>,
+}
+
+impl PcmSample {
+    pub fn total_package_joules(&self) -> f64 {
+        self.package_joules.iter().sum()
+    }
+
+    pub fn total_dram_joules(&self) -> f64 {
+        self.dram_joules.iter().sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PcmSummary {
+    pub samples: usize,
+    pub sockets: usize,
+    pub duration: Duration,
+    pub package_joules: f64,
+    pub dram_joules: f64,
+    pub mean_watts: f64,
+}
+
+/// Column positions resolved from PCM's two header rows.
+struct Layout {
+    date: usize,
+    time: usize,
+    frequency: Option<usize>,
+    ipc: Option<usize>,
+    package: Vec<usize>,
+    dram: Vec<usize>,
+}
+
+impl Layout {
+    fn from_headers(groups: &StringRecord, columns: &StringRecord) -> Result<Self, Box<dyn Error>> {
+        let mut current_group = "";
+        let mut date = None;
+        let mut time = None;
+        let mut frequency = None;
+        let mut ipc = None;
+        let mut package = Vec::new();
+        let mut dram = Vec::new();
+
+        for (i, column) in columns.iter().enumerate() {
+            // Older PCM versions only label the first column of each group.
+            let group = groups.get(i).unwrap_or("").trim();
+            if !group.is_empty() {
+                current_group = group;
+            }
+            let column = column.trim();
+
+            if current_group == "System" {
+                match column {
+                    "Date" => date = Some(i),
+                    "Time" => time = Some(i),
+                    "FREQ" => frequency = Some(i),
+                    "IPC" => ipc = Some(i),
+                    _ => {}
+                }
+            } else if let Some(socket) = socket_index(current_group) {
+                match column {
+                    PACKAGE_ENERGY_COLUMN => insert_at(&mut package, socket, i),
+                    DRAM_ENERGY_COLUMN => insert_at(&mut dram, socket, i),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Layout {
+            date: date.ok_or("PCM CSV has no System Date column")?,
+            time: time.ok_or("PCM CSV has no System Time column")?,
+            frequency,
+            ipc,
+            package: flatten_sockets(package, PACKAGE_ENERGY_COLUMN)?,
+            dram: flatten_sockets(dram, DRAM_ENERGY_COLUMN).unwrap_or_default(),
+        })
+    }
+
+    fn parse_row(&self, row: &StringRecord) -> Result<PcmSample, Box<dyn Error>> {
+        let field = |i: usize| row.get(i).unwrap_or("").trim();
+        let number = |i: usize| -> Result<f64, Box<dyn Error>> {
+            field(i)
+                .parse()
+                .map_err(|_| format!("Invalid number '{}' in PCM CSV", field(i)).into())
+        };
+
+        Ok(PcmSample {
+            timestamp: parse_timestamp(field(self.date), field(self.time))?,
+            package_joules: self
+                .package
+                .iter()
+                .map(|&i| number(i))
+                .collect::<Result<_, _>>()?,
+            dram_joules: self
+                .dram
+                .iter()
+                .map(|&i| number(i))
+                .collect::<Result<_, _>>()?,
+            frequency: self.frequency.and_then(|i| field(i).parse().ok()),
+            ipc: self.ipc.and_then(|i| field(i).parse().ok()),
+        })
+    }
+}
+
+/// Socket groups are labelled `SKT0` or `Socket 0` depending on the PCM version.
+fn socket_index(group: &str) -> Option<usize> {
+    let rest = group
+        .strip_prefix("SKT")
+        .or_else(|| group.strip_prefix("Socket"))?;
+    rest.trim().parse().ok()
+}
+
+fn insert_at(columns: &mut Vec<Option<usize>>, socket: usize, column: usize) {
+    if columns.len() <= socket {
+        columns.resize(socket + 1, None);
+    }
+    columns[socket] = Some(column);
+}
+
+fn flatten_sockets(columns: Vec<Option<usize>>, name: &str) -> Result<Vec<usize>, Box<dyn Error>> {
+    if columns.is_empty() {
+        return Err(format!("PCM CSV has no per-socket '{}' column", name).into());
+    }
+    columns
+        .into_iter()
+        .enumerate()
+        .map(|(socket, column)| {
+            column.ok_or_else(|| {
+                format!("PCM CSV is missing '{}' for socket {}", name, socket).into()
+            })
+        })
+        .collect()
+}
+
+/// Parses PCM's `YYYY-MM-DD` date and `HH:MM:SS.mmm` time columns.
+fn parse_timestamp(date: &str, time: &str) -> Result<f64, Box<dyn Error>> {
+    let invalid = || format!("Invalid PCM timestamp '{} {}'", date, time);
+
+    let date_parts: Vec<i64> = date
+        .split('-')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let time_parts: Vec<f64> = time
+        .split(':')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+
+    match (date_parts.as_slice(), time_parts.as_slice()) {
+        ([year, month, day], [hours, minutes, seconds]) => {
+            let days = days_from_civil(*year, *month, *day);
+            Ok(days as f64 * 86_400.0 + hours * 3_600.0 + minutes * 60.0 + seconds)
+        }
+        _ => Err(invalid().into()),
+    }
+}
+
+// Howard Hinnant's days_from_civil: days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn delimiter(content: &str) -> u8 {
+    let header = content.lines().next().unwrap_or("");
+    if header.matches(';').count() > header.matches(',').count() {
+        b';'
+    } else {
+        b','
+    }
+}
+
+pub fn parse_pcm_csv(content: &str) -> Result<Vec<PcmSample>, Box<dyn Error>> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter(content))
+        .from_reader(content.as_bytes());
+    let mut records = reader.records();
+
+    let groups = records.next().ok_or("PCM CSV is empty")??;
+    let columns = records.next().ok_or("PCM CSV has only one header row")??;
+    let layout = Layout::from_headers(&groups, &columns)?;
+
+    let mut samples = Vec::new();
+    for record in records {
+        let record = record?;
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        samples.push(layout.parse_row(&record)?);
+    }
+    Ok(samples)
+}
+
+pub fn parse_pcm_file(path: &Path) -> Result<Vec<PcmSample>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    parse_pcm_csv(&content).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+/// Mean distance between consecutive rows, in seconds, or the nominal interval for fewer
+/// than two rows.
+fn sample_interval(samples: &[PcmSample]) -> f64 {
+    match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) if samples.len() > 1 => {
+            (last.timestamp - first.timestamp) / (samples.len() - 1) as f64
+        }
+        _ => SAMPLE_INTERVAL.as_secs_f64(),
+    }
+}
+
+/// Totals a measurement file. Each row covers one sampling interval, so the window spans
+/// one interval more than the distance between the first and last timestamps.
+pub fn summarize(samples: &[PcmSample]) -> PcmSummary {
+    let duration = match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) => {
+            let span = last.timestamp - first.timestamp + sample_interval(samples);
+            Duration::from_secs_f64(span.max(0.0))
+        }
+        _ => Duration::ZERO,
+    };
+
+    let package_joules: f64 = samples.iter().map(PcmSample::total_package_joules).sum();
+    let dram_joules: f64 = samples.iter().map(PcmSample::total_dram_joules).sum();
+    let secs = duration.as_secs_f64();
+
+    PcmSummary {
+        samples: samples.len(),
+        sockets: samples.first().map_or(0, |s| s.package_joules.len()),
+        duration,
+        package_joules,
+        dram_joules,
+        mean_watts: if secs > 0.0 {
+            package_joules / secs
+        } else {
+            0.0
+        },
+    }
+}
+
+/// Converts PCM rows into the monitor-independent per-interval samples.
+pub fn to_energy_samples(samples: &[PcmSample]) -> Vec<EnergySample> {
+    let Some(first) = samples.first() else {
+        return Vec::new();
+    };
+    let interval = sample_interval(samples);
+
+    samples
+        .iter()
+        .map(|sample| EnergySample {
+            elapsed: Duration::from_secs_f64(
+                (sample.timestamp - first.timestamp + interval).max(0.0),
+            ),
+            package_joules: sample.total_package_joules(),
+            core_joules: None,
+            dram_joules: (!sample.dram_joules.is_empty()).then(|| sample.total_dram_joules()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Current PCM: every column of a group is labelled, sockets are `Socket N`.
+    const SINGLE_SOCKET: &str = "\
+System;System;System;System;Socket 0;Socket 0;Socket 0
+Date;Time;IPC;FREQ;EXEC;Proc Energy (Joules);DRAM Energy (Joules)
+2024-03-01;10:00:00.000;1.50;2.10;0.80;4.00;0.50
+2024-03-01;10:00:00.100;1.40;2.00;0.70;6.00;0.70
+";
+
+    const DUAL_SOCKET: &str = "\
+System,System,System,System,SKT0,SKT0,SKT1,SKT1
+Date,Time,IPC,FREQ,Proc Energy (Joules),DRAM Energy (Joules),Proc Energy (Joules),DRAM Energy (Joules)
+2024-03-01,10:00:00.000,1.0,2.0,3.0,0.25,5.0,0.75
+2024-03-01,10:00:00.100,1.0,2.0,4.0,0.25,6.0,0.75
+2024-03-01,10:00:00.200,1.0,2.0,5.0,0.25,7.0,0.75
+";
+
+    /// Older PCM only labels the first column of each group.
+    const FIRST_COLUMN_LABELS: &str = "\
+System;;;;SKT0;;SKT1;
+Date;Time;IPC;FREQ;Proc Energy (Joules);DRAM Energy (Joules);Proc Energy (Joules);DRAM Energy (Joules)
+2024-03-01;23:59:59.900;1.0;2.0;1.0;0.5;2.0;0.5
+
+2024-03-02;00:00:00.000;1.0;2.0;1.0;0.5;2.0;0.5
+";
+
+    /// Timestamps are around 1.7e9 s, so differences of them are only good to about 1e-7.
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6 * expected.abs().max(1.0),
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn parses_single_socket_rows() {
+        let samples = parse_pcm_csv(SINGLE_SOCKET).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].package_joules, vec![4.0]);
+        assert_eq!(samples[0].dram_joules, vec![0.5]);
+        assert_eq!(samples[0].ipc, Some(1.5));
+        assert_eq!(samples[0].frequency, Some(2.1));
+        assert_close(samples[1].timestamp - samples[0].timestamp, 0.1);
+    }
+
+    #[test]
+    fn parses_dual_socket_rows_and_summarizes_them() {
+        let samples = parse_pcm_csv(DUAL_SOCKET).unwrap();
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples[1].package_joules, vec![4.0, 6.0]);
+        assert_eq!(samples[1].dram_joules, vec![0.25, 0.75]);
+
+        let summary = summarize(&samples);
+        assert_eq!(summary.samples, 3);
+        assert_eq!(summary.sockets, 2);
+        assert_close(summary.package_joules, 30.0);
+        assert_close(summary.dram_joules, 3.0);
+        // Two intervals between the first and last row, plus the one the first row covers.
+        assert_close(summary.duration.as_secs_f64(), 0.3);
+        assert_close(summary.mean_watts, 100.0);
+    }
+
+    #[test]
+    fn carries_group_labels_forward_for_older_headers() {
+        let samples = parse_pcm_csv(FIRST_COLUMN_LABELS).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].package_joules, vec![1.0, 2.0]);
+        assert_eq!(samples[0].dram_joules, vec![0.5, 0.5]);
+        // The rows straddle midnight.
+        assert_close(samples[1].timestamp - samples[0].timestamp, 0.1);
+    }
+
+    #[test]
+    fn a_single_row_covers_the_nominal_interval() {
+        let samples = parse_pcm_csv(SINGLE_SOCKET).unwrap();
+        let summary = summarize(&samples[..1]);
+        assert_eq!(summary.duration, SAMPLE_INTERVAL);
+        assert_close(summary.mean_watts, 40.0);
+        assert_eq!(to_energy_samples(&samples[..1])[0].elapsed, SAMPLE_INTERVAL);
+    }
+
+    #[test]
+    fn rejects_headers_without_required_columns() {
+        let no_date = "System;Socket 0\nTime;Proc Energy (Joules)\n";
+        assert!(parse_pcm_csv(no_date).is_err());
+        let no_energy = "System;System;Socket 0\nDate;Time;IPC\n";
+        assert!(parse_pcm_csv(no_energy).is_err());
+        assert!(parse_pcm_csv("").is_err());
+    }
+
+    #[test]
+    fn detects_the_delimiter_from_the_first_header_row() {
+        assert_eq!(delimiter(SINGLE_SOCKET), b';');
+        assert_eq!(delimiter(DUAL_SOCKET), b',');
+        assert_eq!(delimiter(""), b',');
+    }
+
+    #[test]
+    fn socket_groups_are_numbered() {
+        assert_eq!(socket_index("SKT0"), Some(0));
+        assert_eq!(socket_index("Socket 1"), Some(1));
+        assert_eq!(socket_index("System"), None);
+    }
+
+    #[test]
+    fn counts_days_from_the_unix_epoch() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(2024, 2, 29), 19_782);
+        assert_close(
+            parse_timestamp("1970-01-02", "01:00:30.5").unwrap(),
+            86_400.0 + 3_600.0 + 30.5,
+        );
+        assert!(parse_timestamp("2024-03", "10:00:00").is_err());
+    }
+}
@@ -0,0 +1,338 @@
+use super::monitor::{EnergySample, Measurement, PowerMonitor};
+use super::sampler::{Sampled, Sampler};
+use async_trait::async_trait;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+const POWERCAP_ROOT: &str = "/sys/class/powercap";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaplDomain {
+    Package,
+    Core,
+    Dram,
+}
+
+impl RaplDomain {
+    fn from_zone_name(name: &str) -> Option<Self> {
+        if name.starts_with("package") {
+            Some(RaplDomain::Package)
+        } else if name == "core" {
+            Some(RaplDomain::Core)
+        } else if name == "dram" {
+            Some(RaplDomain::Dram)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RaplZone {
+    pub domain: RaplDomain,
+    pub energy_path: PathBuf,
+    pub max_energy_range_uj: u64,
+}
+
+/// Finds every `intel-rapl:` zone below `root` that exposes a package, core or DRAM counter.
+///
+/// `intel-rapl-mmio:` zones are skipped: they report the same package a second time through
+/// MMIO, so counting them would double the package energy.
+pub fn discover_zones(root: &Path) -> io::Result<Vec<RaplZone>> {
+    let mut zones = Vec::new();
+
+    for entry in fs::read_dir(root)? {
+        let path = entry?.path();
+        let is_rapl_zone = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("intel-rapl:"));
+        if !is_rapl_zone || !path.join("energy_uj").exists() {
+            continue;
+        }
+
+        let name = fs::read_to_string(path.join("name"))?;
+        if let Some(domain) = RaplDomain::from_zone_name(name.trim()) {
+            zones.push(RaplZone {
+                domain,
+                energy_path: path.join("energy_uj"),
+                max_energy_range_uj: read_counter(&path.join("max_energy_range_uj"))?,
+            });
+        }
+    }
+
+    zones.sort_by(|a, b| a.energy_path.cmp(&b.energy_path));
+    Ok(zones)
+}
+
+/// Energy in µJ between two readings of a counter that wraps at `max_energy_range_uj`.
+pub fn counter_delta(start: u64, end: u64, max_energy_range_uj: u64) -> u64 {
+    if end >= start {
+        end - start
+    } else {
+        max_energy_range_uj - start + end
+    }
+}
+
+fn read_counter(path: &Path) -> io::Result<u64> {
+    fs::read_to_string(path)?.trim().parse().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), e),
+        )
+    })
+}
+
+struct Counters {
+    zones: Vec<RaplZone>,
+    last: Vec<u64>,
+    totals_uj: Vec<u64>,
+    /// Package total so far, readable while the sampler task owns the counters.
+    package_uj: Arc<AtomicU64>,
+    samples: Vec<EnergySample>,
+    start_time: Instant,
+}
+
+impl Counters {
+    fn open(zones: Vec<RaplZone>) -> io::Result<Self> {
+        let last = zones
+            .iter()
+            .map(|zone| read_counter(&zone.energy_path))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Counters {
+            totals_uj: vec![0; zones.len()],
+            package_uj: Arc::new(AtomicU64::new(0)),
+            zones,
+            last,
+            samples: Vec::new(),
+            start_time: Instant::now(),
+        })
+    }
+
+    fn joules(&self, values_uj: &[u64], domain: RaplDomain) -> Option<f64> {
+        let mut found = false;
+        let mut total = 0;
+        for (zone, value) in self.zones.iter().zip(values_uj) {
+            if zone.domain == domain {
+                found = true;
+                total += value;
+            }
+        }
+        found.then_some(total as f64 / 1_000_000.0)
+    }
+}
+
+impl Sampled for Counters {
+    // Reading at every interval keeps each delta below one wrap of the counter.
+    fn sample(&mut self) -> io::Result<()> {
+        let mut interval_uj = vec![0; self.zones.len()];
+
+        for (i, zone) in self.zones.iter().enumerate() {
+            let now = read_counter(&zone.energy_path)?;
+            interval_uj[i] = counter_delta(self.last[i], now, zone.max_energy_range_uj);
+            self.totals_uj[i] += interval_uj[i];
+            self.last[i] = now;
+        }
+        let package_uj = self
+            .zones
+            .iter()
+            .zip(&self.totals_uj)
+            .filter(|(zone, _)| zone.domain == RaplDomain::Package)
+            .map(|(_, total)| total)
+            .sum();
+        self.package_uj.store(package_uj, Ordering::Relaxed);
+
+        self.samples.push(EnergySample {
+            elapsed: self.start_time.elapsed(),
+            package_joules: self
+                .joules(&interval_uj, RaplDomain::Package)
+                .unwrap_or(0.0),
+            core_joules: self.joules(&interval_uj, RaplDomain::Core),
+            dram_joules: self.joules(&interval_uj, RaplDomain::Dram),
+        });
+        Ok(())
+    }
+}
+
+struct ActiveMeasurement {
+    name: String,
+    started_at: SystemTime,
+    package_uj: Arc<AtomicU64>,
+    sampler: Sampler<Counters>,
+}
+
+/// Reads the powercap energy counters the kernel exposes for Intel RAPL.
+///
+/// The sysfs root is configurable so the monitor can run against a fake directory tree.
+pub struct RaplMonitor {
+    root: PathBuf,
+    sample_interval: Duration,
+    active: Option<ActiveMeasurement>,
+}
+
+#[async_trait]
+impl PowerMonitor for RaplMonitor {
+    fn name(&self) -> &'static str {
+        "rapl"
+    }
+
+    async fn start(&mut self, measurement_name: &str) -> Result<(), Box<dyn Error>> {
+        if self.active.is_some() {
+            return Err("RAPL measurement already running".into());
+        }
+
+        let zones = discover_zones(&self.root)?;
+        if !zones.iter().any(|zone| zone.domain == RaplDomain::Package) {
+            return Err(format!("No RAPL package zones found in {}", self.root.display()).into());
+        }
+
+        let counters = Counters::open(zones)?;
+        let package_uj = counters.package_uj.clone();
+
+        self.active = Some(ActiveMeasurement {
+            name: measurement_name.to_string(),
+            started_at: SystemTime::now(),
+            package_uj,
+            sampler: Sampler::start(counters, self.sample_interval),
+        });
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<Measurement, Box<dyn Error>> {
+        let active = self
+            .active
+            .take()
+            .ok_or("RAPL measurement was not started")?;
+
+        let counters = active.sampler.stop().await?;
+
+        let mut measurement = Measurement::new(
+            active.name,
+            self.name(),
+            active.started_at,
+            counters.start_time.elapsed(),
+        );
+        measurement.package_joules = counters.joules(&counters.totals_uj, RaplDomain::Package);
+        measurement.core_joules = counters.joules(&counters.totals_uj, RaplDomain::Core);
+        measurement.dram_joules = counters.joules(&counters.totals_uj, RaplDomain::Dram);
+        measurement.samples = counters.samples;
+
+        Ok(measurement)
+    }
+
+    fn energy_so_far(&self) -> Option<f64> {
+        let active = self.active.as_ref()?;
+        Some(active.package_uj.load(Ordering::Relaxed) as f64 / 1_000_000.0)
+    }
+}
+
+impl RaplMonitor {
+    pub fn new() -> Self {
+        Self::with_root(POWERCAP_ROOT)
+    }
+
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        RaplMonitor {
+            root: root.into(),
+            sample_interval: Duration::from_millis(100),
+            active: None,
+        }
+    }
+
+    pub fn sample_interval(mut self, interval: Duration) -> Self {
+        self.sample_interval = interval;
+        self
+    }
+}
+
+impl Default for RaplMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_RANGE_UJ: u64 = 1_000_000_000;
+
+    /// A powercap tree with one package zone and its DRAM subzone, which the kernel lists
+    /// next to the package, the MMIO view of the same package, and a zone that isn't RAPL.
+    fn fake_powercap(test: &str, package_uj: u64, dram_uj: u64) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("rapl_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        write_zone(&root.join("intel-rapl:0"), "package-0", package_uj);
+        write_zone(&root.join("intel-rapl:0:0"), "dram", dram_uj);
+        write_zone(&root.join("intel-rapl-mmio:0"), "package-0", package_uj);
+        write_zone(&root.join("dtpm:0"), "package-0", 0);
+        root
+    }
+
+    fn write_zone(dir: &Path, name: &str, energy_uj: u64) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("name"), format!("{}\n", name)).unwrap();
+        fs::write(
+            dir.join("max_energy_range_uj"),
+            format!("{}\n", MAX_RANGE_UJ),
+        )
+        .unwrap();
+        set_energy(dir, energy_uj);
+    }
+
+    fn set_energy(dir: &Path, energy_uj: u64) {
+        fs::write(dir.join("energy_uj"), format!("{}\n", energy_uj)).unwrap();
+    }
+
+    #[test]
+    fn discovers_package_and_dram_zones() {
+        let root = fake_powercap("discover", 0, 0);
+        let zones = discover_zones(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let domains: Vec<RaplDomain> = zones.iter().map(|zone| zone.domain).collect();
+        assert_eq!(domains, vec![RaplDomain::Package, RaplDomain::Dram]);
+        assert_eq!(zones[0].energy_path, root.join("intel-rapl:0/energy_uj"));
+        assert!(zones
+            .iter()
+            .all(|zone| zone.max_energy_range_uj == MAX_RANGE_UJ));
+    }
+
+    #[test]
+    fn counter_delta_handles_wraps() {
+        assert_eq!(counter_delta(100, 300, 1000), 200);
+        assert_eq!(counter_delta(900, 100, 1000), 200);
+        assert_eq!(counter_delta(500, 500, 1000), 0);
+    }
+
+    #[tokio::test]
+    async fn measures_energy_between_start_and_stop() {
+        let root = fake_powercap("cycle", MAX_RANGE_UJ - 500_000, 1_000_000);
+        let mut monitor = RaplMonitor::with_root(&root).sample_interval(Duration::from_millis(10));
+
+        monitor.start("cycle.csv").await.unwrap();
+        // The package counter wraps inside the window.
+        set_energy(&root.join("intel-rapl:0"), 1_500_000);
+        set_energy(&root.join("intel-rapl:0:0"), 1_500_000);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(monitor.energy_so_far(), Some(2.0));
+        let measurement = monitor.stop().await.unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(measurement.name, "cycle.csv");
+        assert_eq!(measurement.monitor, "rapl");
+        assert_eq!(measurement.package_joules, Some(2.0));
+        assert_eq!(measurement.dram_joules, Some(0.5));
+        assert_eq!(measurement.core_joules, None);
+        assert!(!measurement.samples.is_empty());
+        let sampled: f64 = measurement.samples.iter().map(|s| s.package_joules).sum();
+        assert_eq!(sampled, 2.0);
+        assert!(monitor.stop().await.is_err());
+    }
+}
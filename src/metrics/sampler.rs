@@ -0,0 +1,103 @@
+use std::error::Error;
+use std::io;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// State that is read periodically while a measurement window is open.
+pub trait Sampled: Send + 'static {
+    fn sample(&mut self) -> io::Result<()>;
+}
+
+/// Samples a state in a background task until stopped.
+///
+/// The state is expected to hold its baseline reading when handed over, so the first sample
+/// is taken one interval after the start. One more sample is taken when the sampler stops,
+/// covering the tail of the window.
+pub struct Sampler<T> {
+    stop_tx: oneshot::Sender<()>,
+    task: JoinHandle<io::Result<T>>,
+}
+
+impl<T: Sampled> Sampler<T> {
+    pub fn start(mut state: T, interval: Duration) -> Self {
+        let (stop_tx, mut stop_rx) = oneshot::channel();
+
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately.
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => state.sample()?,
+                    _ = &mut stop_rx => break,
+                }
+            }
+            state.sample()?;
+            Ok(state)
+        });
+
+        Sampler { stop_tx, task }
+    }
+
+    /// Takes the final sample and hands the state back.
+    pub async fn stop(self) -> Result<T, Box<dyn Error>> {
+        let _ = self.stop_tx.send(());
+        Ok(self.task.await??)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter {
+        samples: usize,
+        fail: bool,
+    }
+
+    impl Sampled for Counter {
+        fn sample(&mut self) -> io::Result<()> {
+            if self.fail {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "gone"));
+            }
+            self.samples += 1;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn skips_the_immediate_tick_and_samples_once_on_stop() {
+        let counter = Counter {
+            samples: 0,
+            fail: false,
+        };
+        let sampler = Sampler::start(counter, Duration::from_secs(3600));
+        tokio::task::yield_now().await;
+
+        assert_eq!(sampler.stop().await.unwrap().samples, 1);
+    }
+
+    #[tokio::test]
+    async fn samples_every_interval_until_stopped() {
+        let counter = Counter {
+            samples: 0,
+            fail: false,
+        };
+        let sampler = Sampler::start(counter, Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(sampler.stop().await.unwrap().samples > 2);
+    }
+
+    #[tokio::test]
+    async fn a_failed_sample_is_returned_from_stop() {
+        let counter = Counter {
+            samples: 0,
+            fail: true,
+        };
+        let sampler = Sampler::start(counter, Duration::from_secs(3600));
+
+        assert!(sampler.stop().await.is_err());
+    }
+}
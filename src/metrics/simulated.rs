@@ -0,0 +1,213 @@
+use super::monitor::{EnergySample, Measurement, PowerMonitor};
+use super::sampler::{Sampled, Sampler};
+use async_trait::async_trait;
+use std::error::Error;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use sysinfo::{CpuExt, System, SystemExt};
+
+/// How the simulated monitor turns elapsed time into package power.
+#[derive(Debug, Clone, Copy)]
+pub enum PowerModel {
+    /// Constant draw, fully deterministic.
+    Fixed { watts: f64 },
+    /// Idle draw plus a per-core cost for every core kept busy, measured from CPU time.
+    CpuTime {
+        idle_watts: f64,
+        watts_per_core: f64,
+    },
+}
+
+struct Trace {
+    model: PowerModel,
+    system: System,
+    last_sample: Instant,
+    package_joules: f64,
+    /// `package_joules` in microjoules, readable while the sampler task owns the trace.
+    package_uj: Arc<AtomicU64>,
+    samples: Vec<EnergySample>,
+    start_time: Instant,
+}
+
+impl Trace {
+    fn open(model: PowerModel) -> Self {
+        let mut system = System::new();
+        system.refresh_cpu();
+        let now = Instant::now();
+
+        Trace {
+            model,
+            system,
+            last_sample: now,
+            package_joules: 0.0,
+            package_uj: Arc::new(AtomicU64::new(0)),
+            samples: Vec::new(),
+            start_time: now,
+        }
+    }
+
+    fn watts(&mut self) -> f64 {
+        match self.model {
+            PowerModel::Fixed { watts } => watts,
+            PowerModel::CpuTime {
+                idle_watts,
+                watts_per_core,
+            } => {
+                self.system.refresh_cpu();
+                let cores = self.system.cpus().len() as f64;
+                let busy_cores = self.system.global_cpu_info().cpu_usage() as f64 / 100.0 * cores;
+                idle_watts + watts_per_core * busy_cores
+            }
+        }
+    }
+}
+
+impl Sampled for Trace {
+    fn sample(&mut self) -> io::Result<()> {
+        let now = Instant::now();
+        let interval = now.duration_since(self.last_sample).as_secs_f64();
+        let joules = self.watts() * interval;
+
+        self.package_joules += joules;
+        self.package_uj.store(
+            (self.package_joules * 1_000_000.0) as u64,
+            Ordering::Relaxed,
+        );
+        self.last_sample = now;
+        self.samples.push(EnergySample {
+            elapsed: now.duration_since(self.start_time),
+            package_joules: joules,
+            core_joules: None,
+            dram_joules: None,
+        });
+        Ok(())
+    }
+}
+
+struct ActiveMeasurement {
+    name: String,
+    started_at: SystemTime,
+    package_uj: Arc<AtomicU64>,
+    sampler: Sampler<Trace>,
+}
+
+/// Synthetic power source for machines without PCM or RAPL access, e.g. laptops and CI containers.
+pub struct SimulatedMonitor {
+    model: PowerModel,
+    sample_interval: Duration,
+    active: Option<ActiveMeasurement>,
+}
+
+#[async_trait]
+impl PowerMonitor for SimulatedMonitor {
+    /// Matches the name the monitor is selected by, so results tell the two models apart.
+    fn name(&self) -> &'static str {
+        match self.model {
+            PowerModel::Fixed { .. } => "simulated-fixed",
+            PowerModel::CpuTime { .. } => "simulated",
+        }
+    }
+
+    async fn start(&mut self, measurement_name: &str) -> Result<(), Box<dyn Error>> {
+        if self.active.is_some() {
+            return Err("Simulated measurement already running".into());
+        }
+
+        let trace = Trace::open(self.model);
+        let package_uj = trace.package_uj.clone();
+
+        self.active = Some(ActiveMeasurement {
+            name: measurement_name.to_string(),
+            started_at: SystemTime::now(),
+            package_uj,
+            sampler: Sampler::start(trace, self.sample_interval),
+        });
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<Measurement, Box<dyn Error>> {
+        let active = self
+            .active
+            .take()
+            .ok_or("Simulated measurement was not started")?;
+
+        let trace = active.sampler.stop().await?;
+
+        let mut measurement = Measurement::new(
+            active.name,
+            self.name(),
+            active.started_at,
+            trace.last_sample.duration_since(trace.start_time),
+        );
+        measurement.package_joules = Some(trace.package_joules);
+        measurement.samples = trace.samples;
+
+        Ok(measurement)
+    }
+
+    fn energy_so_far(&self) -> Option<f64> {
+        let active = self.active.as_ref()?;
+        Some(active.package_uj.load(Ordering::Relaxed) as f64 / 1_000_000.0)
+    }
+}
+
+impl SimulatedMonitor {
+    pub fn new(model: PowerModel) -> Self {
+        SimulatedMonitor {
+            model,
+            sample_interval: System::MINIMUM_CPU_UPDATE_INTERVAL,
+            active: None,
+        }
+    }
+
+    /// CPU-time driven model with rough desktop-class figures.
+    pub fn cpu_time() -> Self {
+        Self::new(PowerModel::CpuTime {
+            idle_watts: 15.0,
+            watts_per_core: 8.0,
+        })
+    }
+
+    pub fn fixed(watts: f64) -> Self {
+        Self::new(PowerModel::Fixed { watts })
+    }
+
+    pub fn sample_interval(mut self, interval: Duration) -> Self {
+        self.sample_interval = interval;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::create_monitor;
+    use std::path::Path;
+
+    #[test]
+    fn each_model_reports_the_name_it_is_created_by() {
+        for name in ["simulated", "simulated-fixed"] {
+            assert_eq!(create_monitor(name, Path::new(".")).unwrap().name(), name);
+        }
+    }
+
+    #[tokio::test]
+    async fn fixed_model_yields_its_watts_over_the_window() {
+        let mut monitor = SimulatedMonitor::fixed(50.0).sample_interval(Duration::from_millis(10));
+
+        monitor.start("fixed.csv").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(monitor.energy_so_far().unwrap() > 0.0);
+        let measurement = monitor.stop().await.unwrap();
+
+        let secs = measurement.duration.as_secs_f64();
+        assert!(secs >= 0.1);
+        let joules = measurement.package_joules.unwrap();
+        assert!((joules - 50.0 * secs).abs() < 1e-9);
+        assert!((measurement.mean_watts().unwrap() - 50.0).abs() < 1e-9);
+        let sampled: f64 = measurement.samples.iter().map(|s| s.package_joules).sum();
+        assert!((sampled - joules).abs() < 1e-9);
+    }
+}
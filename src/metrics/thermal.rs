@@ -0,0 +1,241 @@
+use super::sampler::{Sampled, Sampler};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const THERMAL_ROOT: &str = "/sys/class/thermal";
+const CPU_ROOT: &str = "/sys/devices/system/cpu";
+
+/// How far temperature and frequency may move within one window before the run is flagged.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DriftThresholds {
+    pub max_temp_drift_c: f64,
+    pub max_freq_drift_pct: f64,
+}
+
+impl Default for DriftThresholds {
+    fn default() -> Self {
+        DriftThresholds {
+            max_temp_drift_c: 5.0,
+            max_freq_drift_pct: 10.0,
+        }
+    }
+}
+
+/// Temperature and CPU frequency observed during one measurement window.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThermalTelemetry {
+    pub samples: usize,
+    pub min_temp_c: Option<f64>,
+    pub max_temp_c: Option<f64>,
+    pub min_freq_mhz: Option<f64>,
+    pub max_freq_mhz: Option<f64>,
+    pub mean_freq_mhz: Option<f64>,
+    pub governors: Vec<String>,
+}
+
+impl ThermalTelemetry {
+    pub fn temp_drift_c(&self) -> Option<f64> {
+        Some(self.max_temp_c? - self.min_temp_c?)
+    }
+
+    pub fn freq_drift_pct(&self) -> Option<f64> {
+        let max = self.max_freq_mhz?;
+        (max > 0.0).then(|| (max - self.min_freq_mhz.unwrap_or(max)) / max * 100.0)
+    }
+
+    pub fn drifted(&self, thresholds: &DriftThresholds) -> bool {
+        self.temp_drift_c()
+            .is_some_and(|drift| drift > thresholds.max_temp_drift_c)
+            || self
+                .freq_drift_pct()
+                .is_some_and(|drift| drift > thresholds.max_freq_drift_pct)
+    }
+}
+
+/// The distinct cpufreq governors currently active across all CPUs.
+pub fn current_governors() -> Vec<String> {
+    let sources = Sources::discover(Path::new(THERMAL_ROOT), Path::new(CPU_ROOT));
+    let governors: BTreeSet<String> = sources
+        .governors
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|governor| governor.trim().to_string())
+        .collect();
+    governors.into_iter().collect()
+}
+
+/// Paths of every sysfs file read on each tick, resolved once when the sampler starts.
+struct Sources {
+    temperatures: Vec<PathBuf>,
+    frequencies: Vec<PathBuf>,
+    governors: Vec<PathBuf>,
+}
+
+impl Sources {
+    fn discover(thermal_root: &Path, cpu_root: &Path) -> Self {
+        let temperatures = matching_dirs(thermal_root, |name| name.starts_with("thermal_zone"))
+            .into_iter()
+            .map(|zone| zone.join("temp"))
+            .filter(|path| path.exists())
+            .collect();
+
+        let cpufreq_dirs: Vec<PathBuf> = matching_dirs(cpu_root, |name| {
+            name.strip_prefix("cpu")
+                .is_some_and(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+        })
+        .into_iter()
+        .map(|cpu| cpu.join("cpufreq"))
+        .collect();
+
+        Sources {
+            temperatures,
+            frequencies: existing(&cpufreq_dirs, "scaling_cur_freq"),
+            governors: existing(&cpufreq_dirs, "scaling_governor"),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.temperatures.is_empty() && self.frequencies.is_empty()
+    }
+}
+
+fn matching_dirs(root: &Path, matches: impl Fn(&str) -> bool) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(&matches)
+        })
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn existing(dirs: &[PathBuf], file_name: &str) -> Vec<PathBuf> {
+    dirs.iter()
+        .map(|dir| dir.join(file_name))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+// Thermal zones report millidegrees Celsius and cpufreq reports kHz.
+fn read_number(path: &Path) -> Option<f64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn widen(min: &mut Option<f64>, max: &mut Option<f64>, value: f64) {
+    *min = Some(min.map_or(value, |min| min.min(value)));
+    *max = Some(max.map_or(value, |max| max.max(value)));
+}
+
+struct Trace {
+    sources: Sources,
+    telemetry: ThermalTelemetry,
+    freq_sum_mhz: f64,
+    freq_samples: usize,
+    governors: BTreeSet<String>,
+}
+
+impl Sampled for Trace {
+    fn sample(&mut self) -> io::Result<()> {
+        let max_temp_c = self
+            .sources
+            .temperatures
+            .iter()
+            .filter_map(|path| read_number(path))
+            .map(|millidegrees| millidegrees / 1000.0)
+            .reduce(f64::max);
+
+        let frequencies: Vec<f64> = self
+            .sources
+            .frequencies
+            .iter()
+            .filter_map(|path| read_number(path))
+            .map(|khz| khz / 1000.0)
+            .collect();
+
+        for path in &self.sources.governors {
+            if let Ok(governor) = fs::read_to_string(path) {
+                self.governors.insert(governor.trim().to_string());
+            }
+        }
+
+        let telemetry = &mut self.telemetry;
+        telemetry.samples += 1;
+        if let Some(temp) = max_temp_c {
+            widen(&mut telemetry.min_temp_c, &mut telemetry.max_temp_c, temp);
+        }
+        if !frequencies.is_empty() {
+            let mean = frequencies.iter().sum::<f64>() / frequencies.len() as f64;
+            widen(
+                &mut telemetry.min_freq_mhz,
+                &mut telemetry.max_freq_mhz,
+                mean,
+            );
+            self.freq_sum_mhz += mean;
+            self.freq_samples += 1;
+        }
+        Ok(())
+    }
+}
+
+impl Trace {
+    fn finish(mut self) -> ThermalTelemetry {
+        if self.freq_samples > 0 {
+            self.telemetry.mean_freq_mhz = Some(self.freq_sum_mhz / self.freq_samples as f64);
+        }
+        self.telemetry.governors = self.governors.into_iter().collect();
+        self.telemetry
+    }
+}
+
+/// Records package temperature, mean CPU frequency and the active governor while a window is open.
+pub struct ThermalSampler {
+    sampler: Sampler<Trace>,
+}
+
+impl ThermalSampler {
+    /// Returns `None` when the machine exposes neither thermal zones nor cpufreq.
+    pub fn start(interval: Duration) -> Option<Self> {
+        Self::start_with_roots(Path::new(THERMAL_ROOT), Path::new(CPU_ROOT), interval)
+    }
+
+    pub fn start_with_roots(
+        thermal_root: &Path,
+        cpu_root: &Path,
+        interval: Duration,
+    ) -> Option<Self> {
+        let sources = Sources::discover(thermal_root, cpu_root);
+        if sources.is_empty() {
+            return None;
+        }
+
+        let mut trace = Trace {
+            sources,
+            telemetry: ThermalTelemetry::default(),
+            freq_sum_mhz: 0.0,
+            freq_samples: 0,
+            governors: BTreeSet::new(),
+        };
+        // The baseline reading. Unreadable sources are skipped, so sampling never fails.
+        let _ = trace.sample();
+
+        Some(ThermalSampler {
+            sampler: Sampler::start(trace, interval),
+        })
+    }
+
+    pub async fn stop(self) -> Result<ThermalTelemetry, Box<dyn Error>> {
+        Ok(self.sampler.stop().await?.finish())
+    }
+}